@@ -2,16 +2,108 @@
 
 pub use self::cgtoken::CgTokenRef;
 
+pub mod psp22;
+pub mod time;
+
 #[ink::contract]
 mod cgtoken {
-    use ink::storage::Mapping;
+    use ink::{
+        prelude::vec::Vec,
+        storage::{
+            traits::{Packed, StorageKey},
+            Mapping,
+        },
+    };
+
+    use crate::{
+        psp22::{PSP22Error, PSP22},
+        time::{days, DAY_MS},
+    };
+
+    const DEFAULT_UNBONDING_PERIOD: Timestamp = days(14);
+    // upper bound on queued chunks so withdrawing stays cheap
+    const MAX_UNBONDING_CHUNKS: u32 = 32;
+    // fixed point scale of `reward_per_token_stored`
+    const REWARD_PRECISION: Balance = 1_000_000_000_000;
+
+    // a portion of stake that left voting power and becomes free at `unlock_at`
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct UnbondingChunk {
+        pub amount: Balance,
+        pub unlock_at: Timestamp,
+    }
+
+    // tokens granted to `beneficiary` that unlock linearly over `duration`
+    // after `start`, with nothing unlocking before the `cliff` passed
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct VestingSchedule {
+        pub total: Balance,
+        pub released: Balance,
+        pub start: Timestamp,
+        // milliseconds after `start`
+        pub cliff: Timestamp,
+        pub duration: Timestamp,
+        pub revocable: bool,
+    }
+
+    // value of a per-account history (stake, voting power or delegate)
+    // from `timestamp` on
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Checkpoint<T> {
+        pub timestamp: Timestamp,
+        pub value: T,
+    }
+
+    // checkpoints of an account indexed from 0
+    type History<T> = Mapping<(AccountId, u32), Checkpoint<T>>;
 
     #[ink(storage)]
     pub struct CgToken {
+        owner: AccountId,
         total_supply: Balance,
+        max_supply: Balance,
+        minters: Mapping<AccountId, ()>,
         balances: Mapping<AccountId, Balance>,
+        allowances: Mapping<(AccountId, AccountId), Balance>,
         staked_balances: Mapping<AccountId, Balance>,
+        checkpoints: History<Balance>,
+        num_checkpoints: Mapping<AccountId, u32>,
+        // own stake unless delegated away, plus stake delegated to the account
+        power_checkpoints: History<Balance>,
+        num_power_checkpoints: Mapping<AccountId, u32>,
+        delegates: Mapping<AccountId, AccountId>,
+        delegation_checkpoints: History<Option<AccountId>>,
+        num_delegation_checkpoints: Mapping<AccountId, u32>,
         staked_at: Mapping<AccountId, Timestamp>,
+        unbonding: Mapping<AccountId, Vec<UnbondingChunk>>,
+        // milliseconds, like `block_timestamp()`
+        unbonding_period: Timestamp,
+        min_stake: Balance,
+        max_stake_per_account: Balance,
+        total_staked: Balance,
+        // tokens emitted per day across all stakers, paid out of `reward_reserve`
+        reward_rate: Balance,
+        reward_reserve: Balance,
+        reward_per_token_stored: Balance,
+        // rewards scaled by `REWARD_PRECISION` that did not divide evenly
+        // into `reward_per_token_stored`, carried into the next emission
+        reward_dust: Balance,
+        last_reward_update: Timestamp,
+        reward_per_token_paid: Mapping<AccountId, Balance>,
+        rewards: Mapping<AccountId, Balance>,
+        vesting: Mapping<AccountId, VestingSchedule>,
     }
 
     #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
@@ -20,7 +112,25 @@ mod cgtoken {
         InsufficientBalance,
         AlreadyStaked,
         NotStaked,
-        UnstakingPeriodNotElapsed,
+        UnbondingPeriodNotElapsed,
+        TooManyUnbondingChunks,
+        NothingUnbonding,
+        NotOwner,
+        BelowMinStake,
+        AboveMaxStake,
+        InvalidStakingParameters,
+        NoRewards,
+        NotMinter,
+        MaxSupplyExceeded,
+        InsufficientAllowance,
+        VestingExists,
+        NoVestingSchedule,
+        InvalidVestingSchedule,
+        NothingToRelease,
+        NotRevocable,
+        InvalidDelegate,
+        NotDelegated,
+        ZeroAmount,
     }
 
     #[ink(event)]
@@ -34,180 +144,1720 @@ mod cgtoken {
     }
 
     #[ink(event)]
-    pub struct Unstaked {
+    pub struct Unbonded {
+        #[ink(topic)]
+        staker: AccountId,
+        #[ink(topic)]
+        amount: Balance,
+        unlock_at: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct Withdrawn {
+        #[ink(topic)]
+        staker: AccountId,
+        #[ink(topic)]
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Approval {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        spender: AccountId,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Minted {
+        #[ink(topic)]
+        to: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Burned {
+        #[ink(topic)]
+        from: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct MinterChanged {
+        #[ink(topic)]
+        minter: AccountId,
+        allowed: bool,
+    }
+
+    #[ink(event)]
+    pub struct OwnerChanged {
+        #[ink(topic)]
+        owner: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct UnbondingPeriodChanged {
+        unbonding_period_ms: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct MinStakeChanged {
+        min_stake: Balance,
+    }
+
+    #[ink(event)]
+    pub struct MaxStakePerAccountChanged {
+        max_stake_per_account: Balance,
+    }
+
+    #[ink(event)]
+    pub struct RewardRateChanged {
+        reward_rate: Balance,
+    }
+
+    #[ink(event)]
+    pub struct RewardsFunded {
+        #[ink(topic)]
+        funder: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct RewardsDistributed {
+        #[ink(topic)]
+        distributor: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct RewardsClaimed {
         #[ink(topic)]
         staker: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct VestingCreated {
+        #[ink(topic)]
+        beneficiary: AccountId,
+        amount: Balance,
+        start: Timestamp,
+        cliff_ms: Timestamp,
+        duration_ms: Timestamp,
+        revocable: bool,
+    }
+
+    #[ink(event)]
+    pub struct VestingReleased {
+        #[ink(topic)]
+        beneficiary: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct VestingRevoked {
         #[ink(topic)]
+        beneficiary: AccountId,
         amount: Balance,
     }
 
+    #[ink(event)]
+    pub struct DelegateChanged {
+        #[ink(topic)]
+        delegator: AccountId,
+        #[ink(topic)]
+        from_delegate: Option<AccountId>,
+        #[ink(topic)]
+        to_delegate: Option<AccountId>,
+    }
+
     impl CgToken {
+        /// Mints `initial_supply` to the caller. Later mints can never push the
+        /// supply above `max_supply`. `owner` administers minters and the
+        /// staking parameters.
         #[ink(constructor)]
-        pub fn new(total_supply: Balance) -> Self {
+        pub fn new(initial_supply: Balance, max_supply: Balance, owner: AccountId) -> Self {
+            assert!(initial_supply <= max_supply);
             let mut balances = Mapping::default();
             let caller = Self::env().caller();
-            balances.insert(caller, &total_supply);
+            balances.insert(caller, &initial_supply);
             Self {
-                total_supply: total_supply,
-                balances: balances,
+                owner,
+                total_supply: initial_supply,
+                max_supply,
+                minters: Mapping::default(),
+                balances,
+                allowances: Mapping::default(),
                 staked_balances: Mapping::default(),
+                checkpoints: Mapping::default(),
+                num_checkpoints: Mapping::default(),
+                power_checkpoints: Mapping::default(),
+                num_power_checkpoints: Mapping::default(),
+                delegates: Mapping::default(),
+                delegation_checkpoints: Mapping::default(),
+                num_delegation_checkpoints: Mapping::default(),
                 staked_at: Mapping::default(),
+                unbonding: Mapping::default(),
+                unbonding_period: DEFAULT_UNBONDING_PERIOD,
+                min_stake: 0,
+                max_stake_per_account: Balance::MAX,
+                total_staked: 0,
+                reward_rate: 0,
+                reward_reserve: 0,
+                reward_per_token_stored: 0,
+                reward_dust: 0,
+                last_reward_update: Self::env().block_timestamp(),
+                reward_per_token_paid: Mapping::default(),
+                rewards: Mapping::default(),
+                vesting: Mapping::default(),
             }
         }
 
         #[ink(message)]
-        pub fn total_supply(&self) -> Balance {
-            self.total_supply
+        pub fn owner(&self) -> AccountId {
+            self.owner
         }
 
         #[ink(message)]
-        pub fn balance_of(&self, owner: AccountId) -> Balance {
-            self.balances.get(owner).unwrap_or_default()
+        pub fn unbonding_period(&self) -> Timestamp {
+            self.unbonding_period
         }
 
         #[ink(message)]
-        pub fn staked_balance_of(&self, staker: AccountId) -> Balance {
-            self.staked_balances.get(staker).unwrap_or_default()
+        pub fn min_stake(&self) -> Balance {
+            self.min_stake
         }
 
         #[ink(message)]
-        pub fn staked_at(&self, staker: AccountId) -> Timestamp {
-            self.staked_at.get(staker).unwrap_or_default()
+        pub fn max_stake_per_account(&self) -> Balance {
+            self.max_stake_per_account
         }
 
         #[ink(message)]
-        pub fn stake(&mut self, amount: Balance) -> Result<(), Error> {
-            let staker = self.env().caller();
-            let balance = self.balance_of(staker);
+        pub fn set_owner(&mut self, owner: AccountId) -> Result<(), Error> {
+            self.ensure_owner()?;
+            self.owner = owner;
+            self.env().emit_event(OwnerChanged { owner });
+            Ok(())
+        }
 
-            let current_timestamp = self.env().block_timestamp();
+        /// Only affects chunks unbonded after the change.
+        #[ink(message)]
+        pub fn set_unbonding_period(
+            &mut self,
+            unbonding_period_ms: Timestamp,
+        ) -> Result<(), Error> {
+            self.ensure_owner()?;
+            self.unbonding_period = unbonding_period_ms;
+            self.env().emit_event(UnbondingPeriodChanged {
+                unbonding_period_ms,
+            });
+            Ok(())
+        }
 
-            if amount > balance {
-                return Err(Error::InsufficientBalance);
+        #[ink(message)]
+        pub fn set_min_stake(&mut self, min_stake: Balance) -> Result<(), Error> {
+            self.ensure_owner()?;
+            if min_stake > self.max_stake_per_account {
+                return Err(Error::InvalidStakingParameters);
             }
+            self.min_stake = min_stake;
+            self.env().emit_event(MinStakeChanged { min_stake });
+            Ok(())
+        }
 
-            if self.staked_balance_of(staker) > 0 {
-                return Err(Error::AlreadyStaked);
+        #[ink(message)]
+        pub fn set_max_stake_per_account(
+            &mut self,
+            max_stake_per_account: Balance,
+        ) -> Result<(), Error> {
+            self.ensure_owner()?;
+            if max_stake_per_account < self.min_stake {
+                return Err(Error::InvalidStakingParameters);
             }
+            self.max_stake_per_account = max_stake_per_account;
+            self.env().emit_event(MaxStakePerAccountChanged {
+                max_stake_per_account,
+            });
+            Ok(())
+        }
 
-            let timestamp = self.env().block_timestamp();
+        /// Changes the daily emission. Rewards accrued so far are settled at
+        /// the old rate first.
+        #[ink(message)]
+        pub fn set_reward_rate(&mut self, reward_rate: Balance) -> Result<(), Error> {
+            self.ensure_owner()?;
+            self.update_reward(None);
+            self.reward_rate = reward_rate;
+            self.env().emit_event(RewardRateChanged { reward_rate });
+            Ok(())
+        }
 
-            self.balances.insert(staker, &(balance - amount));
-            self.staked_balances.insert(staker, &amount);
-            self.staked_at.insert(staker, &current_timestamp);
+        #[ink(message)]
+        pub fn reward_rate(&self) -> Balance {
+            self.reward_rate
+        }
 
-            self.env().emit_event(Staked {
-                staker,
+        #[ink(message)]
+        pub fn reward_reserve(&self) -> Balance {
+            self.reward_reserve
+        }
+
+        #[ink(message)]
+        pub fn total_staked(&self) -> Balance {
+            self.total_staked
+        }
+
+        /// Rewards accrued per staked token, scaled by `REWARD_PRECISION`.
+        #[ink(message)]
+        pub fn reward_per_token(&self) -> Balance {
+            self.accrued_reward_per_token(self.env().block_timestamp())
+                .0
+        }
+
+        #[ink(message)]
+        pub fn pending_rewards(&self, staker: AccountId) -> Balance {
+            self.earned(staker, self.reward_per_token())
+        }
+
+        /// Moves `amount` of the caller's tokens into the reserve that is
+        /// emitted to stakers at `reward_rate`.
+        #[ink(message)]
+        pub fn fund_rewards(&mut self, amount: Balance) -> Result<(), Error> {
+            let funder = self.env().caller();
+            self.debit(funder, amount)?;
+
+            self.update_reward(None);
+            self.reward_reserve += amount;
+
+            self.env().emit_event(RewardsFunded { funder, amount });
+
+            Ok(())
+        }
+
+        /// Hands `amount` of the caller's tokens to the current stakers right
+        /// away, e.g. platform fees pushed in by `CredebilityGuard`. Without
+        /// any stake the tokens are added to the reward reserve instead.
+        #[ink(message)]
+        pub fn distribute_rewards(&mut self, amount: Balance) -> Result<(), Error> {
+            let distributor = self.env().caller();
+            self.debit(distributor, amount)?;
+
+            self.update_reward(None);
+            if self.total_staked == 0 {
+                self.reward_reserve += amount;
+            } else {
+                let (per_token, dust) = split_reward(amount, self.reward_dust, self.total_staked);
+                self.reward_per_token_stored += per_token;
+                self.reward_dust = dust;
+            }
+
+            self.env().emit_event(RewardsDistributed {
+                distributor,
                 amount,
-                timestamp,
             });
 
             Ok(())
         }
 
+        /// Pays the caller's accrued rewards into their free balance.
         #[ink(message)]
-        pub fn unstake(&mut self, amount: Balance) -> Result<(), Error> {
+        pub fn claim_rewards(&mut self) -> Result<Balance, Error> {
             let staker = self.env().caller();
-            let staked_balance = self.staked_balances.get(staker).unwrap_or_default();
-            let staked_at = self.staked_at.get(staker).unwrap_or_default();
+            self.update_reward(Some(staker));
 
-            if amount > staked_balance {
-                return Err(Error::InsufficientBalance);
+            let amount = self.rewards.get(staker).unwrap_or_default();
+            if amount == 0 {
+                return Err(Error::NoRewards);
             }
 
-            let current_timestamp = self.env().block_timestamp();
-            let unstaking_period = 14 * 24 * 60 * 60; // 14 days in seconds
+            self.rewards.remove(staker);
+            let balance = self.balance_of(staker);
+            self.balances.insert(staker, &(balance + amount));
+
+            self.env().emit_event(RewardsClaimed { staker, amount });
+
+            Ok(amount)
+        }
 
-            if current_timestamp < staked_at + unstaking_period {
-                return Err(Error::UnstakingPeriodNotElapsed);
+        #[ink(message)]
+        pub fn total_supply(&self) -> Balance {
+            self.total_supply
+        }
+
+        #[ink(message)]
+        pub fn max_supply(&self) -> Balance {
+            self.max_supply
+        }
+
+        #[ink(message)]
+        pub fn is_minter(&self, account: AccountId) -> bool {
+            self.minters.contains(account)
+        }
+
+        #[ink(message)]
+        pub fn set_minter(&mut self, minter: AccountId, allowed: bool) -> Result<(), Error> {
+            self.ensure_owner()?;
+            if allowed {
+                self.minters.insert(minter, &());
+            } else {
+                self.minters.remove(minter);
             }
+            self.env().emit_event(MinterChanged { minter, allowed });
+            Ok(())
+        }
 
-            let balance = self.balance_of(staker);
-            self.balances.insert(staker, &(balance + amount));
-            self.staked_balances.insert(staker, &(staked_balance - amount));
+        #[ink(message)]
+        pub fn mint(&mut self, to: AccountId, amount: Balance) -> Result<(), Error> {
+            if !self.is_minter(self.env().caller()) {
+                return Err(Error::NotMinter);
+            }
+            let total_supply = self
+                .total_supply
+                .checked_add(amount)
+                .filter(|supply| *supply <= self.max_supply)
+                .ok_or(Error::MaxSupplyExceeded)?;
 
-            self.env().emit_event(Unstaked { staker, amount });
+            self.total_supply = total_supply;
+            let balance = self.balance_of(to);
+            self.balances.insert(to, &(balance + amount));
+
+            self.env().emit_event(Minted { to, amount });
 
             Ok(())
         }
 
+        /// Destroys `amount` of the caller's free balance.
         #[ink(message)]
-        pub fn transfer(&mut self, to: AccountId, value: Balance) -> Result<(), Error> {
+        pub fn burn(&mut self, amount: Balance) -> Result<(), Error> {
             let from = self.env().caller();
-            let from_balance = self.balance_of(from);
-            if from_balance < value {
-                return Err(Error::InsufficientBalance);
+            self.burn_balance(from, amount)
+        }
+
+        /// Destroys `amount` of `from`'s free balance, spending the caller's
+        /// allowance.
+        #[ink(message)]
+        pub fn burn_from(&mut self, from: AccountId, amount: Balance) -> Result<(), Error> {
+            let spender = self.env().caller();
+            let allowance = self.allowance(from, spender);
+            if allowance < amount {
+                return Err(Error::InsufficientAllowance);
             }
+            self.burn_balance(from, amount)?;
+            self.allowances
+                .insert((from, spender), &(allowance - amount));
+            Ok(())
+        }
 
-            self.balances.insert(from, &(from_balance - value));
-            let to_balance = self.balance_of(to);
-            self.balances.insert(to, &(to_balance + value));
+        #[ink(message)]
+        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.allowances.get((owner, spender)).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn approve(&mut self, spender: AccountId, value: Balance) -> Result<(), Error> {
+            let owner = self.env().caller();
+            self.allowances.insert((owner, spender), &value);
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                value,
+            });
             Ok(())
         }
-    }
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
+        #[ink(message)]
+        pub fn balance_of(&self, owner: AccountId) -> Balance {
+            self.balances.get(owner).unwrap_or_default()
+        }
 
-        #[ink::test]
-        fn total_supply_works() {
-            let cgtoken = CgToken::new(100);
-            assert_eq!(cgtoken.total_supply(), 100);
+        /// Free balance that is not held back by an unreleased vesting
+        /// schedule. Locked tokens count as staked first, so staking
+        /// still-vesting tokens does not lock any other tokens.
+        #[ink(message)]
+        pub fn spendable_balance_of(&self, owner: AccountId) -> Balance {
+            let bonded = self.staked_balance_of(owner) + self.unbonding_balance_of(owner);
+            let locked = self.locked_balance_of(owner).saturating_sub(bonded);
+            self.balance_of(owner).saturating_sub(locked)
         }
 
-        #[ink::test]
-        fn balance_of_works() {
-            let cgtoken = CgToken::new(100);
-            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
-            assert_eq!(cgtoken.balance_of(accounts.alice), 100);
-            assert_eq!(cgtoken.balance_of(accounts.bob), 0);
+        #[ink(message)]
+        pub fn locked_balance_of(&self, owner: AccountId) -> Balance {
+            self.vesting
+                .get(owner)
+                .map(|schedule| schedule.total - schedule.released)
+                .unwrap_or_default()
         }
 
-        #[ink::test]
-        fn staking_works() {
-            let mut cgtoken = CgToken::new(100);
-            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+        #[ink(message)]
+        pub fn vesting_schedule_of(&self, beneficiary: AccountId) -> Option<VestingSchedule> {
+            self.vesting.get(beneficiary)
+        }
 
-            assert_eq!(cgtoken.staked_balance_of(accounts.alice), 0);
-            assert_eq!(cgtoken.stake(10), Ok(()));
-            assert_eq!(cgtoken.staked_balance_of(accounts.alice), 10);
+        /// Amount of the schedule of `beneficiary` that has vested at `at`,
+        /// including what was already released.
+        #[ink(message)]
+        pub fn vested_amount(&self, beneficiary: AccountId, at: Timestamp) -> Balance {
+            self.vesting
+                .get(beneficiary)
+                .map(|schedule| vested_at(&schedule, at))
+                .unwrap_or_default()
         }
 
-        #[ink::test]
-        fn unstaking_works() {
-            let mut cgtoken = CgToken::new(100);
-            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+        /// Grants `amount` of the owner's tokens to `beneficiary`. They sit in
+        /// the beneficiary's balance right away, can be staked, and become
+        /// spendable as they vest and get released.
+        #[ink(message)]
+        pub fn create_vesting(
+            &mut self,
+            beneficiary: AccountId,
+            amount: Balance,
+            start: Timestamp,
+            cliff_ms: Timestamp,
+            duration_ms: Timestamp,
+            revocable: bool,
+        ) -> Result<(), Error> {
+            self.ensure_owner()?;
+            if duration_ms == 0 || cliff_ms > duration_ms {
+                return Err(Error::InvalidVestingSchedule);
+            }
+            if self.vesting.contains(beneficiary) {
+                return Err(Error::VestingExists);
+            }
 
-            assert_eq!(cgtoken.staked_balance_of(accounts.alice), 0);
-            assert_eq!(cgtoken.stake(10), Ok(()));
-            assert_eq!(cgtoken.staked_balance_of(accounts.alice), 10);
-            assert_eq!(cgtoken.unstake(5), Ok(()));
-            assert_eq!(cgtoken.staked_balance_of(accounts.alice), 5);
+            self.debit(self.owner, amount)?;
+            let balance = self.balance_of(beneficiary);
+            self.balances.insert(beneficiary, &(balance + amount));
+            self.vesting.insert(
+                beneficiary,
+                &VestingSchedule {
+                    total: amount,
+                    released: 0,
+                    start,
+                    cliff: cliff_ms,
+                    duration: duration_ms,
+                    revocable,
+                },
+            );
+
+            self.env().emit_event(VestingCreated {
+                beneficiary,
+                amount,
+                start,
+                cliff_ms,
+                duration_ms,
+                revocable,
+            });
+
+            Ok(())
         }
 
-        #[ink::test]
-        fn unstaking_with_insufficient_balance_fails() {
-            let mut cgtoken = CgToken::new(100);
-            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+        /// Unlocks everything of the caller's schedule that vested so far.
+        #[ink(message)]
+        pub fn release(&mut self) -> Result<Balance, Error> {
+            let beneficiary = self.env().caller();
+            let mut schedule = self
+                .vesting
+                .get(beneficiary)
+                .ok_or(Error::NoVestingSchedule)?;
 
-            assert_eq!(cgtoken.staked_balance_of(accounts.alice), 0);
-            assert_eq!(cgtoken.stake(10), Ok(()));
-            assert_eq!(cgtoken.staked_balance_of(accounts.alice), 10);
-            assert_eq!(cgtoken.unstake(15), Err(Error::InsufficientBalance));
+            let amount = vested_at(&schedule, self.env().block_timestamp()) - schedule.released;
+            if amount == 0 {
+                return Err(Error::NothingToRelease);
+            }
+
+            schedule.released += amount;
+            self.store_vesting(beneficiary, &schedule);
+
+            self.env().emit_event(VestingReleased {
+                beneficiary,
+                amount,
+            });
+
+            Ok(amount)
         }
 
-        #[ink::test]
-        fn unstaking_before_period_fails() {
-            let mut cgtoken = CgToken::new(100);
-            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+        /// Stops a revocable schedule and returns its unvested part to the
+        /// owner, taking it from the beneficiary's free balance first, then
+        /// from their stake and finally from their unbonding queue.
+        #[ink(message)]
+        pub fn revoke_vesting(&mut self, beneficiary: AccountId) -> Result<Balance, Error> {
+            self.ensure_owner()?;
+            let mut schedule = self
+                .vesting
+                .get(beneficiary)
+                .ok_or(Error::NoVestingSchedule)?;
+            if !schedule.revocable {
+                return Err(Error::NotRevocable);
+            }
 
-            assert_eq!(cgtoken.staked_balance_of(accounts.alice), 0);
-            assert_eq!(cgtoken.stake(10), Ok(()));
-            assert_eq!(cgtoken.staked_balance_of(accounts.alice), 10);
-            assert_eq!(cgtoken.unstake(5), Ok(()));
-            assert_eq!(cgtoken.staked_balance_of(accounts.alice), 5);
-            assert_eq!(cgtoken.unstake(5), Err(Error::UnstakingPeriodNotElapsed));
+            let vested = vested_at(&schedule, self.env().block_timestamp());
+            let amount = schedule.total - vested;
+            schedule.total = vested;
+            self.store_vesting(beneficiary, &schedule);
+            let seized = self.seize(beneficiary, amount);
+            let owner_balance = self.balance_of(self.owner);
+            self.balances.insert(self.owner, &(owner_balance + seized));
+
+            self.env().emit_event(VestingRevoked {
+                beneficiary,
+                amount: seized,
+            });
+
+            Ok(seized)
+        }
+
+        #[ink(message)]
+        pub fn staked_balance_of(&self, staker: AccountId) -> Balance {
+            self.staked_balances.get(staker).unwrap_or_default()
+        }
+
+        /// Staked balance of `staker` as it was at `timestamp`, including
+        /// changes made in a block with exactly that timestamp.
+        #[ink(message)]
+        pub fn staked_balance_at(&self, staker: AccountId, timestamp: Timestamp) -> Balance {
+            checkpoint_at(
+                &self.checkpoints,
+                self.num_checkpoints(staker),
+                staker,
+                timestamp,
+            )
+            .unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn num_checkpoints(&self, staker: AccountId) -> u32 {
+            self.num_checkpoints.get(staker).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn checkpoint(&self, staker: AccountId, index: u32) -> Option<Checkpoint<Balance>> {
+            self.checkpoints.get((staker, index))
+        }
+
+        #[ink(message)]
+        pub fn delegate_of(&self, delegator: AccountId) -> Option<AccountId> {
+            self.delegates.get(delegator)
+        }
+
+        #[ink(message)]
+        pub fn delegate_at(&self, delegator: AccountId, timestamp: Timestamp) -> Option<AccountId> {
+            checkpoint_at(
+                &self.delegation_checkpoints,
+                self.num_delegation_checkpoints
+                    .get(delegator)
+                    .unwrap_or_default(),
+                delegator,
+                timestamp,
+            )
+            .flatten()
+        }
+
+        #[ink(message)]
+        pub fn voting_power_of(&self, account: AccountId) -> Balance {
+            self.voting_power_at(account, Timestamp::MAX)
+        }
+
+        /// Own stake of `account` unless it was delegated away, plus all stake
+        /// delegated to `account`, as it was at `timestamp`.
+        #[ink(message)]
+        pub fn voting_power_at(&self, account: AccountId, timestamp: Timestamp) -> Balance {
+            checkpoint_at(
+                &self.power_checkpoints,
+                self.num_power_checkpoints.get(account).unwrap_or_default(),
+                account,
+                timestamp,
+            )
+            .unwrap_or_default()
+        }
+
+        /// Lends the caller's staked voting power to `to`. Delegated power is
+        /// not passed on if `to` delegates as well.
+        #[ink(message)]
+        pub fn delegate(&mut self, to: AccountId) -> Result<(), Error> {
+            let delegator = self.env().caller();
+            if to == delegator {
+                return Err(Error::InvalidDelegate);
+            }
+            let from_delegate = self.delegates.get(delegator);
+            if from_delegate == Some(to) {
+                return Ok(());
+            }
+
+            let staked = self.staked_balance_of(delegator);
+            self.shift_voting_power(Some(from_delegate.unwrap_or(delegator)), Some(to), staked);
+            self.delegates.insert(delegator, &to);
+            self.record_delegate(delegator, Some(to));
+
+            self.env().emit_event(DelegateChanged {
+                delegator,
+                from_delegate,
+                to_delegate: Some(to),
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn undelegate(&mut self) -> Result<(), Error> {
+            let delegator = self.env().caller();
+            let from_delegate = self.delegates.get(delegator).ok_or(Error::NotDelegated)?;
+
+            let staked = self.staked_balance_of(delegator);
+            self.shift_voting_power(Some(from_delegate), Some(delegator), staked);
+            self.delegates.remove(delegator);
+            self.record_delegate(delegator, None);
+
+            self.env().emit_event(DelegateChanged {
+                delegator,
+                from_delegate: Some(from_delegate),
+                to_delegate: None,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn staked_at(&self, staker: AccountId) -> Timestamp {
+            self.staked_at.get(staker).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn unbonding_of(&self, staker: AccountId) -> Vec<UnbondingChunk> {
+            self.unbonding.get(staker).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn unbonding_balance_of(&self, staker: AccountId) -> Balance {
+            self.unbonding_of(staker)
+                .iter()
+                .map(|chunk| chunk.amount)
+                .sum()
+        }
+
+        #[ink(message)]
+        pub fn stake(&mut self, amount: Balance) -> Result<(), Error> {
+            let staker = self.env().caller();
+            let balance = self.balance_of(staker);
+
+            let current_timestamp = self.env().block_timestamp();
+
+            if amount > balance {
+                return Err(Error::InsufficientBalance);
+            }
+
+            if self.staked_balance_of(staker) > 0 {
+                return Err(Error::AlreadyStaked);
+            }
+
+            if amount < self.min_stake {
+                return Err(Error::BelowMinStake);
+            }
+
+            if amount > self.max_stake_per_account {
+                return Err(Error::AboveMaxStake);
+            }
+
+            let timestamp = self.env().block_timestamp();
+
+            self.update_reward(Some(staker));
+            self.balances.insert(staker, &(balance - amount));
+            self.set_staked_balance(staker, amount);
+            self.total_staked += amount;
+            self.staked_at.insert(staker, &current_timestamp);
+
+            self.env().emit_event(Staked {
+                staker,
+                amount,
+                timestamp,
+            });
+
+            Ok(())
+        }
+
+        /// Moves `amount` out of the caller's stake into the unbonding queue.
+        /// The tokens stop counting as voting power right away and can be
+        /// withdrawn with `withdraw_unbonded` once the unbonding period passed.
+        /// The remaining stake must either be zero or at least `min_stake`.
+        #[ink(message)]
+        pub fn unbond(&mut self, amount: Balance) -> Result<(), Error> {
+            let staker = self.env().caller();
+            let staked_balance = self.staked_balance_of(staker);
+
+            // an empty chunk would take up one of the capped slots
+            if amount == 0 {
+                return Err(Error::ZeroAmount);
+            }
+
+            if amount > staked_balance {
+                return Err(Error::InsufficientBalance);
+            }
+
+            let remaining = staked_balance - amount;
+            if remaining > 0 && remaining < self.min_stake {
+                return Err(Error::BelowMinStake);
+            }
+
+            let mut chunks = self.unbonding_of(staker);
+            if chunks.len() as u32 >= MAX_UNBONDING_CHUNKS {
+                return Err(Error::TooManyUnbondingChunks);
+            }
+
+            let unlock_at = self.env().block_timestamp() + self.unbonding_period;
+            chunks.push(UnbondingChunk { amount, unlock_at });
+
+            self.update_reward(Some(staker));
+            self.set_staked_balance(staker, remaining);
+            self.total_staked -= amount;
+            self.unbonding.insert(staker, &chunks);
+
+            self.env().emit_event(Unbonded {
+                staker,
+                amount,
+                unlock_at,
+            });
+
+            Ok(())
+        }
+
+        /// Releases every matured unbonding chunk of the caller back to the
+        /// free balance and returns the amount released.
+        #[ink(message)]
+        pub fn withdraw_unbonded(&mut self) -> Result<Balance, Error> {
+            let staker = self.env().caller();
+            let chunks = self.unbonding_of(staker);
+
+            if chunks.is_empty() {
+                return Err(Error::NothingUnbonding);
+            }
+
+            let current_timestamp = self.env().block_timestamp();
+            let (matured, pending): (Vec<UnbondingChunk>, Vec<UnbondingChunk>) = chunks
+                .into_iter()
+                .partition(|chunk| chunk.unlock_at <= current_timestamp);
+
+            if matured.is_empty() {
+                return Err(Error::UnbondingPeriodNotElapsed);
+            }
+            let amount: Balance = matured.iter().map(|chunk| chunk.amount).sum();
+
+            if pending.is_empty() {
+                self.unbonding.remove(staker);
+            } else {
+                self.unbonding.insert(staker, &pending);
+            }
+            let balance = self.balance_of(staker);
+            self.balances.insert(staker, &(balance + amount));
+
+            self.env().emit_event(Withdrawn { staker, amount });
+
+            Ok(amount)
+        }
+
+        #[ink(message)]
+        pub fn transfer(&mut self, to: AccountId, value: Balance) -> Result<(), Error> {
+            let from = self.env().caller();
+            self.debit(from, value)?;
+
+            let to_balance = self.balance_of(to);
+            self.balances.insert(to, &(to_balance + value));
+            Ok(())
+        }
+
+        // Writes the staked balance and records it as a checkpoint, folding
+        // multiple changes within one block into a single checkpoint.
+        fn set_staked_balance(&mut self, staker: AccountId, staked: Balance) {
+            let previous = self.staked_balance_of(staker);
+            let target = self.delegates.get(staker).unwrap_or(staker);
+            if staked > previous {
+                self.shift_voting_power(None, Some(target), staked - previous);
+            } else {
+                self.shift_voting_power(Some(target), None, previous - staked);
+            }
+
+            self.staked_balances.insert(staker, &staked);
+            let timestamp = self.env().block_timestamp();
+            push_checkpoint(
+                &mut self.checkpoints,
+                &mut self.num_checkpoints,
+                staker,
+                Checkpoint {
+                    timestamp,
+                    value: staked,
+                },
+            );
+        }
+
+        fn shift_voting_power(
+            &mut self,
+            from: Option<AccountId>,
+            to: Option<AccountId>,
+            amount: Balance,
+        ) {
+            if amount == 0 {
+                return;
+            }
+            let timestamp = self.env().block_timestamp();
+            if let Some(from) = from {
+                let value = self.voting_power_of(from) - amount;
+                push_checkpoint(
+                    &mut self.power_checkpoints,
+                    &mut self.num_power_checkpoints,
+                    from,
+                    Checkpoint { timestamp, value },
+                );
+            }
+            if let Some(to) = to {
+                let value = self.voting_power_of(to) + amount;
+                push_checkpoint(
+                    &mut self.power_checkpoints,
+                    &mut self.num_power_checkpoints,
+                    to,
+                    Checkpoint { timestamp, value },
+                );
+            }
+        }
+
+        fn record_delegate(&mut self, delegator: AccountId, delegate: Option<AccountId>) {
+            let timestamp = self.env().block_timestamp();
+            push_checkpoint(
+                &mut self.delegation_checkpoints,
+                &mut self.num_delegation_checkpoints,
+                delegator,
+                Checkpoint {
+                    timestamp,
+                    value: delegate,
+                },
+            );
+        }
+
+        // Takes `amount` out of the spendable balance of `account`.
+        fn debit(&mut self, account: AccountId, amount: Balance) -> Result<(), Error> {
+            if amount > self.spendable_balance_of(account) {
+                return Err(Error::InsufficientBalance);
+            }
+            let balance = self.balance_of(account);
+            self.balances.insert(account, &(balance - amount));
+            Ok(())
+        }
+
+        // Removes `amount` from `account` regardless of locks, draining the
+        // free balance, then the stake, then the unbonding queue. Returns
+        // what could actually be taken, which is less than `amount` if the
+        // account holds less.
+        fn seize(&mut self, account: AccountId, amount: Balance) -> Balance {
+            let balance = self.balance_of(account);
+            let from_balance = amount.min(balance);
+            self.balances.insert(account, &(balance - from_balance));
+            let mut remaining = amount - from_balance;
+
+            let staked = self.staked_balance_of(account);
+            let from_stake = remaining.min(staked);
+            if from_stake > 0 {
+                self.update_reward(Some(account));
+                self.set_staked_balance(account, staked - from_stake);
+                self.total_staked -= from_stake;
+                remaining -= from_stake;
+            }
+
+            if remaining > 0 {
+                let mut chunks = self.unbonding_of(account);
+                while remaining > 0 {
+                    let Some(chunk) = chunks.last_mut() else {
+                        break;
+                    };
+                    let taken = remaining.min(chunk.amount);
+                    chunk.amount -= taken;
+                    remaining -= taken;
+                    if chunk.amount == 0 {
+                        chunks.pop();
+                    }
+                }
+                self.unbonding.insert(account, &chunks);
+            }
+            amount - remaining
+        }
+
+        fn store_vesting(&mut self, beneficiary: AccountId, schedule: &VestingSchedule) {
+            if schedule.released == schedule.total {
+                self.vesting.remove(beneficiary);
+            } else {
+                self.vesting.insert(beneficiary, schedule);
+            }
+        }
+
+        fn burn_balance(&mut self, from: AccountId, amount: Balance) -> Result<(), Error> {
+            self.debit(from, amount)?;
+            self.total_supply -= amount;
+
+            self.env().emit_event(Burned { from, amount });
+
+            Ok(())
+        }
+
+        // Returns the accumulator at `now`, the amount emitted from the
+        // reserve since the last update and the dust left over.
+        fn accrued_reward_per_token(&self, now: Timestamp) -> (Balance, Balance, Balance) {
+            if self.total_staked == 0 {
+                return (self.reward_per_token_stored, 0, self.reward_dust);
+            }
+            let elapsed = now.saturating_sub(self.last_reward_update) as Balance;
+            let emitted = (self.reward_rate * elapsed / DAY_MS as Balance).min(self.reward_reserve);
+            let (per_token, dust) = split_reward(emitted, self.reward_dust, self.total_staked);
+            (self.reward_per_token_stored + per_token, emitted, dust)
+        }
+
+        fn earned(&self, staker: AccountId, reward_per_token: Balance) -> Balance {
+            let paid = self.reward_per_token_paid.get(staker).unwrap_or_default();
+            let owed = self.rewards.get(staker).unwrap_or_default();
+            owed + self.staked_balance_of(staker) * (reward_per_token - paid) / REWARD_PRECISION
+        }
+
+        // Settles emissions up to now and checkpoints `staker` before their
+        // stake changes.
+        fn update_reward(&mut self, staker: Option<AccountId>) {
+            let now = self.env().block_timestamp();
+            let (reward_per_token, emitted, dust) = self.accrued_reward_per_token(now);
+            self.reward_per_token_stored = reward_per_token;
+            self.reward_dust = dust;
+            self.reward_reserve -= emitted;
+            self.last_reward_update = now;
+
+            if let Some(staker) = staker {
+                let earned = self.earned(staker, reward_per_token);
+                self.rewards.insert(staker, &earned);
+                self.reward_per_token_paid.insert(staker, &reward_per_token);
+            }
+        }
+
+        fn ensure_owner(&self) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            Ok(())
+        }
+    }
+
+    impl PSP22 for CgToken {
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> Balance {
+            CgToken::balance_of(self, owner)
+        }
+
+        #[ink(message)]
+        fn transfer(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            Ok(CgToken::transfer(self, to, value)?)
+        }
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            let spender = self.env().caller();
+            let allowance = self.allowance(from, spender);
+            if allowance < value {
+                return Err(PSP22Error::InsufficientAllowance);
+            }
+            self.debit(from, value)?;
+            let to_balance = self.balance_of(to);
+            self.balances.insert(to, &(to_balance + value));
+            self.allowances
+                .insert((from, spender), &(allowance - value));
+            Ok(())
+        }
+    }
+
+    impl From<Error> for PSP22Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InsufficientBalance => PSP22Error::InsufficientBalance,
+                Error::InsufficientAllowance => PSP22Error::InsufficientAllowance,
+                _ => PSP22Error::Custom(ink::prelude::format!("{error:?}")),
+            }
+        }
+    }
+
+    // Shares `amount` plus the `dust` left by earlier shares among
+    // `total_staked` tokens. Returns the reward per token and the new dust,
+    // both scaled by `REWARD_PRECISION`.
+    fn split_reward(amount: Balance, dust: Balance, total_staked: Balance) -> (Balance, Balance) {
+        let scaled = amount * REWARD_PRECISION + dust;
+        (scaled / total_staked, scaled % total_staked)
+    }
+
+    // Binary search for the last of `count` checkpoints of `account` that is
+    // not after `timestamp`.
+    fn checkpoint_at<T, K>(
+        checkpoints: &Mapping<(AccountId, u32), Checkpoint<T>, K>,
+        count: u32,
+        account: AccountId,
+        timestamp: Timestamp,
+    ) -> Option<T>
+    where
+        T: Packed,
+        K: StorageKey,
+    {
+        let mut low = 0;
+        let mut high = count;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match checkpoints.get((account, mid)) {
+                Some(checkpoint) if checkpoint.timestamp <= timestamp => low = mid + 1,
+                _ => high = mid,
+            }
+        }
+        let index = low.checked_sub(1)?;
+        checkpoints
+            .get((account, index))
+            .map(|checkpoint| checkpoint.value)
+    }
+
+    // Appends a checkpoint, folding multiple changes within one block into a
+    // single checkpoint.
+    fn push_checkpoint<T, K, C>(
+        checkpoints: &mut Mapping<(AccountId, u32), Checkpoint<T>, K>,
+        counts: &mut Mapping<AccountId, u32, C>,
+        account: AccountId,
+        checkpoint: Checkpoint<T>,
+    ) where
+        T: Packed,
+        K: StorageKey,
+        C: StorageKey,
+    {
+        let count = counts.get(account).unwrap_or_default();
+        let last = count
+            .checked_sub(1)
+            .and_then(|index| checkpoints.get((account, index)));
+        match last {
+            Some(last) if last.timestamp == checkpoint.timestamp => {
+                checkpoints.insert((account, count - 1), &checkpoint);
+            }
+            _ => {
+                checkpoints.insert((account, count), &checkpoint);
+                counts.insert(account, &(count + 1));
+            }
+        }
+    }
+
+    fn vested_at(schedule: &VestingSchedule, at: Timestamp) -> Balance {
+        if at < schedule.start + schedule.cliff {
+            return 0;
+        }
+        let elapsed = at - schedule.start;
+        if elapsed >= schedule.duration {
+            return schedule.total;
+        }
+        schedule.total * elapsed as Balance / schedule.duration as Balance
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn total_supply_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let cgtoken = CgToken::new(100, 100, accounts.alice);
+            assert_eq!(cgtoken.total_supply(), 100);
+        }
+
+        #[ink::test]
+        fn balance_of_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let cgtoken = CgToken::new(100, 100, accounts.alice);
+            assert_eq!(cgtoken.balance_of(accounts.alice), 100);
+            assert_eq!(cgtoken.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn staking_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+
+            assert_eq!(cgtoken.staked_balance_of(accounts.alice), 0);
+            assert_eq!(cgtoken.stake(10), Ok(()));
+            assert_eq!(cgtoken.staked_balance_of(accounts.alice), 10);
+        }
+
+        #[ink::test]
+        fn unbonding_removes_voting_power() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+
+            assert_eq!(cgtoken.stake(10), Ok(()));
+            assert_eq!(cgtoken.unbond(4), Ok(()));
+            assert_eq!(cgtoken.staked_balance_of(accounts.alice), 6);
+            assert_eq!(cgtoken.unbonding_balance_of(accounts.alice), 4);
+            assert_eq!(cgtoken.balance_of(accounts.alice), 90);
+            assert_eq!(
+                cgtoken.unbonding_of(accounts.alice),
+                vec![UnbondingChunk {
+                    amount: 4,
+                    unlock_at: DEFAULT_UNBONDING_PERIOD,
+                }]
+            );
+        }
+
+        #[ink::test]
+        fn unbonding_with_insufficient_balance_fails() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+
+            assert_eq!(cgtoken.stake(10), Ok(()));
+            assert_eq!(cgtoken.unbond(15), Err(Error::InsufficientBalance));
+            assert_eq!(cgtoken.staked_balance_of(accounts.alice), 10);
+        }
+
+        #[ink::test]
+        fn unbonding_nothing_fails() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+
+            assert_eq!(cgtoken.stake(10), Ok(()));
+            assert_eq!(cgtoken.unbond(0), Err(Error::ZeroAmount));
+            assert!(cgtoken.unbonding_of(accounts.alice).is_empty());
+        }
+
+        #[ink::test]
+        fn withdrawing_before_period_fails() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+
+            assert_eq!(cgtoken.withdraw_unbonded(), Err(Error::NothingUnbonding));
+            assert_eq!(cgtoken.stake(10), Ok(()));
+            assert_eq!(cgtoken.unbond(5), Ok(()));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                DEFAULT_UNBONDING_PERIOD - 1,
+            );
+            assert_eq!(
+                cgtoken.withdraw_unbonded(),
+                Err(Error::UnbondingPeriodNotElapsed)
+            );
+        }
+
+        #[ink::test]
+        fn withdrawing_releases_only_matured_chunks() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+
+            assert_eq!(cgtoken.stake(10), Ok(()));
+            assert_eq!(cgtoken.unbond(3), Ok(()));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(100);
+            assert_eq!(cgtoken.unbond(5), Ok(()));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                DEFAULT_UNBONDING_PERIOD,
+            );
+            assert_eq!(cgtoken.withdraw_unbonded(), Ok(3));
+            assert_eq!(cgtoken.balance_of(accounts.alice), 93);
+            assert_eq!(cgtoken.unbonding_balance_of(accounts.alice), 5);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                DEFAULT_UNBONDING_PERIOD + 100,
+            );
+            assert_eq!(cgtoken.withdraw_unbonded(), Ok(5));
+            assert_eq!(cgtoken.balance_of(accounts.alice), 98);
+            assert_eq!(cgtoken.staked_balance_of(accounts.alice), 2);
+            assert!(cgtoken.unbonding_of(accounts.alice).is_empty());
+        }
+
+        #[ink::test]
+        fn unbonding_chunks_are_capped() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+
+            assert_eq!(cgtoken.stake(50), Ok(()));
+            for _ in 0..MAX_UNBONDING_CHUNKS {
+                assert_eq!(cgtoken.unbond(1), Ok(()));
+            }
+            assert_eq!(cgtoken.unbond(1), Err(Error::TooManyUnbondingChunks));
+        }
+
+        #[ink::test]
+        fn staking_parameters_are_owner_only() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(cgtoken.set_unbonding_period(1), Err(Error::NotOwner));
+            assert_eq!(cgtoken.set_min_stake(1), Err(Error::NotOwner));
+            assert_eq!(cgtoken.set_max_stake_per_account(1), Err(Error::NotOwner));
+            assert_eq!(cgtoken.set_owner(accounts.bob), Err(Error::NotOwner));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(cgtoken.set_unbonding_period(7), Ok(()));
+            assert_eq!(cgtoken.set_max_stake_per_account(50), Ok(()));
+            assert_eq!(cgtoken.set_min_stake(5), Ok(()));
+            assert_eq!(cgtoken.unbonding_period(), 7);
+            assert_eq!(cgtoken.min_stake(), 5);
+            assert_eq!(cgtoken.max_stake_per_account(), 50);
+            assert_eq!(
+                cgtoken.set_min_stake(51),
+                Err(Error::InvalidStakingParameters)
+            );
+            assert_eq!(
+                cgtoken.set_max_stake_per_account(4),
+                Err(Error::InvalidStakingParameters)
+            );
+
+            assert_eq!(cgtoken.set_owner(accounts.bob), Ok(()));
+            assert_eq!(cgtoken.owner(), accounts.bob);
+            assert_eq!(cgtoken.set_min_stake(0), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn staking_respects_limits() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+
+            assert_eq!(cgtoken.set_max_stake_per_account(20), Ok(()));
+            assert_eq!(cgtoken.set_min_stake(5), Ok(()));
+            assert_eq!(cgtoken.stake(4), Err(Error::BelowMinStake));
+            assert_eq!(cgtoken.stake(21), Err(Error::AboveMaxStake));
+            assert_eq!(cgtoken.stake(10), Ok(()));
+            assert_eq!(cgtoken.unbond(6), Err(Error::BelowMinStake));
+            assert_eq!(cgtoken.unbond(5), Ok(()));
+            assert_eq!(cgtoken.unbond(5), Ok(()));
+            assert_eq!(cgtoken.staked_balance_of(accounts.alice), 0);
+        }
+
+        #[ink::test]
+        fn unbonding_takes_fourteen_days_of_block_time() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+            let start = 1_700_000_000_000;
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(start);
+            assert_eq!(cgtoken.unbonding_period(), 14 * 24 * 60 * 60 * 1_000);
+            assert_eq!(cgtoken.stake(10), Ok(()));
+            assert_eq!(cgtoken.unbond(10), Ok(()));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                start + crate::time::days(13),
+            );
+            assert_eq!(
+                cgtoken.withdraw_unbonded(),
+                Err(Error::UnbondingPeriodNotElapsed)
+            );
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                start + crate::time::days(14) - 1,
+            );
+            assert_eq!(
+                cgtoken.withdraw_unbonded(),
+                Err(Error::UnbondingPeriodNotElapsed)
+            );
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                start + crate::time::days(14),
+            );
+            assert_eq!(cgtoken.withdraw_unbonded(), Ok(10));
+        }
+
+        #[ink::test]
+        fn unbonding_uses_configured_period() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+
+            assert_eq!(cgtoken.set_unbonding_period(10), Ok(()));
+            assert_eq!(cgtoken.stake(10), Ok(()));
+            assert_eq!(cgtoken.unbond(10), Ok(()));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(9);
+            assert_eq!(
+                cgtoken.withdraw_unbonded(),
+                Err(Error::UnbondingPeriodNotElapsed)
+            );
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(10);
+            assert_eq!(cgtoken.withdraw_unbonded(), Ok(10));
+        }
+
+        #[ink::test]
+        fn rewards_are_emitted_pro_rata() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(10_000, 10_000, accounts.alice);
+
+            assert_eq!(cgtoken.transfer(accounts.bob, 1_000), Ok(()));
+            assert_eq!(cgtoken.fund_rewards(1_000), Ok(()));
+            assert_eq!(cgtoken.set_reward_rate(100), Ok(()));
+            assert_eq!(cgtoken.stake(300), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(cgtoken.stake(100), Ok(()));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(days(2));
+            assert_eq!(cgtoken.pending_rewards(accounts.alice), 150);
+            assert_eq!(cgtoken.pending_rewards(accounts.bob), 50);
+
+            assert_eq!(cgtoken.claim_rewards(), Ok(50));
+            assert_eq!(cgtoken.balance_of(accounts.bob), 950);
+            assert_eq!(cgtoken.pending_rewards(accounts.bob), 0);
+            assert_eq!(cgtoken.claim_rewards(), Err(Error::NoRewards));
+            assert_eq!(cgtoken.reward_reserve(), 800);
+        }
+
+        #[ink::test]
+        fn emission_is_capped_by_reserve() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(10_000, 10_000, accounts.alice);
+
+            assert_eq!(cgtoken.fund_rewards(100), Ok(()));
+            assert_eq!(cgtoken.set_reward_rate(100), Ok(()));
+            assert_eq!(cgtoken.stake(1_000), Ok(()));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(days(5));
+            assert_eq!(cgtoken.pending_rewards(accounts.alice), 100);
+            assert_eq!(cgtoken.claim_rewards(), Ok(100));
+            assert_eq!(cgtoken.reward_reserve(), 0);
+            assert_eq!(cgtoken.balance_of(accounts.alice), 9_000);
+        }
+
+        #[ink::test]
+        fn distribution_remainders_are_carried_forward() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let staked = 3 * REWARD_PRECISION;
+            let mut cgtoken = CgToken::new(staked + 10, staked + 10, accounts.alice);
+            assert_eq!(cgtoken.stake(staked), Ok(()));
+
+            // too little to raise the reward per token on its own
+            assert_eq!(cgtoken.distribute_rewards(1), Ok(()));
+            assert_eq!(cgtoken.reward_per_token(), 0);
+            assert_eq!(cgtoken.distribute_rewards(2), Ok(()));
+            assert_eq!(cgtoken.reward_per_token(), 1);
+            assert_eq!(cgtoken.pending_rewards(accounts.alice), 3);
+        }
+
+        #[ink::test]
+        fn distributed_rewards_go_to_current_stakers() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(10_000, 10_000, accounts.alice);
+
+            assert_eq!(cgtoken.distribute_rewards(40), Ok(()));
+            assert_eq!(cgtoken.reward_reserve(), 40);
+
+            assert_eq!(cgtoken.stake(1_000), Ok(()));
+            assert_eq!(cgtoken.distribute_rewards(60), Ok(()));
+            assert_eq!(cgtoken.pending_rewards(accounts.alice), 60);
+
+            assert_eq!(cgtoken.unbond(1_000), Ok(()));
+            assert_eq!(cgtoken.distribute_rewards(10), Ok(()));
+            assert_eq!(cgtoken.pending_rewards(accounts.alice), 60);
+            assert_eq!(cgtoken.reward_reserve(), 50);
+        }
+
+        #[ink::test]
+        fn reward_rate_is_owner_only() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(cgtoken.set_reward_rate(1), Err(Error::NotOwner));
+            assert_eq!(cgtoken.fund_rewards(1), Err(Error::InsufficientBalance));
+        }
+
+        #[ink::test]
+        fn minting_is_capped_and_restricted() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 150, accounts.alice);
+
+            assert_eq!(cgtoken.max_supply(), 150);
+            assert_eq!(cgtoken.mint(accounts.bob, 10), Err(Error::NotMinter));
+            assert_eq!(cgtoken.set_minter(accounts.alice, true), Ok(()));
+            assert!(cgtoken.is_minter(accounts.alice));
+            assert_eq!(cgtoken.mint(accounts.bob, 50), Ok(()));
+            assert_eq!(cgtoken.balance_of(accounts.bob), 50);
+            assert_eq!(cgtoken.total_supply(), 150);
+            assert_eq!(cgtoken.mint(accounts.bob, 1), Err(Error::MaxSupplyExceeded));
+
+            assert_eq!(cgtoken.set_minter(accounts.alice, false), Ok(()));
+            assert_eq!(cgtoken.mint(accounts.bob, 0), Err(Error::NotMinter));
+        }
+
+        #[ink::test]
+        fn burning_frees_supply() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+
+            assert_eq!(cgtoken.burn(101), Err(Error::InsufficientBalance));
+            assert_eq!(cgtoken.burn(30), Ok(()));
+            assert_eq!(cgtoken.total_supply(), 70);
+            assert_eq!(cgtoken.balance_of(accounts.alice), 70);
+
+            assert_eq!(cgtoken.set_minter(accounts.alice, true), Ok(()));
+            assert_eq!(cgtoken.mint(accounts.alice, 30), Ok(()));
+            assert_eq!(cgtoken.total_supply(), 100);
+        }
+
+        #[ink::test]
+        fn burn_from_spends_allowance() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+
+            assert_eq!(cgtoken.approve(accounts.bob, 20), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                cgtoken.burn_from(accounts.alice, 21),
+                Err(Error::InsufficientAllowance)
+            );
+            assert_eq!(cgtoken.burn_from(accounts.alice, 15), Ok(()));
+            assert_eq!(cgtoken.allowance(accounts.alice, accounts.bob), 5);
+            assert_eq!(cgtoken.balance_of(accounts.alice), 85);
+            assert_eq!(cgtoken.total_supply(), 85);
+        }
+
+        #[ink::test]
+        fn psp22_transfer_from_spends_allowance() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+
+            assert_eq!(cgtoken.approve(accounts.bob, 20), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                PSP22::transfer_from(
+                    &mut cgtoken,
+                    accounts.alice,
+                    accounts.charlie,
+                    21,
+                    Vec::new()
+                ),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+            assert_eq!(
+                PSP22::transfer_from(
+                    &mut cgtoken,
+                    accounts.alice,
+                    accounts.charlie,
+                    15,
+                    Vec::new()
+                ),
+                Ok(())
+            );
+            assert_eq!(cgtoken.allowance(accounts.alice, accounts.bob), 5);
+            assert_eq!(cgtoken.balance_of(accounts.alice), 85);
+            assert_eq!(cgtoken.balance_of(accounts.charlie), 15);
+            assert_eq!(
+                PSP22::transfer(&mut cgtoken, accounts.alice, 16, Vec::new()),
+                Err(PSP22Error::InsufficientBalance)
+            );
+        }
+
+        #[ink::test]
+        fn vesting_unlocks_after_cliff_linearly() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(1_000, 1_000, accounts.alice);
+
+            assert_eq!(
+                cgtoken.create_vesting(accounts.bob, 400, 100, 50, 200, false),
+                Ok(())
+            );
+            assert_eq!(
+                cgtoken.create_vesting(accounts.bob, 1, 0, 0, 1, false),
+                Err(Error::VestingExists)
+            );
+            assert_eq!(cgtoken.vested_amount(accounts.bob, 149), 0);
+            assert_eq!(cgtoken.vested_amount(accounts.bob, 150), 100);
+            assert_eq!(cgtoken.vested_amount(accounts.bob, 200), 200);
+            assert_eq!(cgtoken.vested_amount(accounts.bob, 300), 400);
+            assert_eq!(cgtoken.balance_of(accounts.bob), 400);
+            assert_eq!(cgtoken.spendable_balance_of(accounts.bob), 0);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                cgtoken.transfer(accounts.charlie, 1),
+                Err(Error::InsufficientBalance)
+            );
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(149);
+            assert_eq!(cgtoken.release(), Err(Error::NothingToRelease));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(200);
+            assert_eq!(cgtoken.release(), Ok(200));
+            assert_eq!(cgtoken.transfer(accounts.charlie, 200), Ok(()));
+            assert_eq!(
+                cgtoken.transfer(accounts.charlie, 1),
+                Err(Error::InsufficientBalance)
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(300);
+            assert_eq!(cgtoken.release(), Ok(200));
+            assert_eq!(cgtoken.vesting_schedule_of(accounts.bob), None);
+            assert_eq!(cgtoken.release(), Err(Error::NoVestingSchedule));
+        }
+
+        #[ink::test]
+        fn vesting_tokens_can_be_staked() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(1_000, 1_000, accounts.alice);
+
+            assert_eq!(
+                cgtoken.create_vesting(accounts.bob, 100, 0, 0, 1_000, false),
+                Ok(())
+            );
+            assert_eq!(cgtoken.transfer(accounts.bob, 50), Ok(()));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(cgtoken.stake(100), Ok(()));
+            assert_eq!(cgtoken.staked_balance_of(accounts.bob), 100);
+            assert_eq!(cgtoken.spendable_balance_of(accounts.bob), 50);
+
+            assert_eq!(cgtoken.unbond(100), Ok(()));
+            assert_eq!(cgtoken.spendable_balance_of(accounts.bob), 50);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                DEFAULT_UNBONDING_PERIOD,
+            );
+            assert_eq!(cgtoken.withdraw_unbonded(), Ok(100));
+            assert_eq!(cgtoken.spendable_balance_of(accounts.bob), 50);
+        }
+
+        #[ink::test]
+        fn revoking_returns_unvested_tokens() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(1_000, 1_000, accounts.alice);
+
+            assert_eq!(
+                cgtoken.create_vesting(accounts.bob, 100, 0, 0, 100, false),
+                Ok(())
+            );
+            assert_eq!(
+                cgtoken.revoke_vesting(accounts.bob),
+                Err(Error::NotRevocable)
+            );
+            assert_eq!(
+                cgtoken.create_vesting(accounts.charlie, 100, 0, 0, 100, true),
+                Ok(())
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(cgtoken.stake(80), Ok(()));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(30);
+            assert_eq!(
+                cgtoken.revoke_vesting(accounts.charlie),
+                Err(Error::NotOwner)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(cgtoken.revoke_vesting(accounts.charlie), Ok(70));
+            assert_eq!(cgtoken.balance_of(accounts.alice), 870);
+            assert_eq!(cgtoken.balance_of(accounts.charlie), 0);
+            assert_eq!(cgtoken.staked_balance_of(accounts.charlie), 30);
+            assert_eq!(cgtoken.total_staked(), 30);
+            assert_eq!(cgtoken.locked_balance_of(accounts.charlie), 30);
+        }
+
+        #[ink::test]
+        fn revoking_seizes_across_balance_stake_and_unbonding() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(1_000, 1_000, accounts.alice);
+            assert_eq!(
+                cgtoken.create_vesting(accounts.bob, 100, 0, 0, 100, true),
+                Ok(())
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(40);
+            assert_eq!(cgtoken.release(), Ok(40));
+            assert_eq!(cgtoken.transfer(accounts.charlie, 40), Ok(()));
+            assert_eq!(cgtoken.stake(30), Ok(()));
+            assert_eq!(cgtoken.unbond(10), Ok(()));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(cgtoken.revoke_vesting(accounts.bob), Ok(60));
+            assert_eq!(cgtoken.balance_of(accounts.alice), 960);
+            assert_eq!(cgtoken.balance_of(accounts.bob), 0);
+            assert_eq!(cgtoken.staked_balance_of(accounts.bob), 0);
+            assert_eq!(cgtoken.unbonding_balance_of(accounts.bob), 0);
+            assert_eq!(cgtoken.balance_of(accounts.charlie), 40);
+            assert_eq!(cgtoken.total_supply(), 1_000);
+        }
+
+        #[ink::test]
+        fn staked_balance_is_checkpointed() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(10);
+            assert_eq!(cgtoken.stake(50), Ok(()));
+            assert_eq!(cgtoken.unbond(5), Ok(()));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(20);
+            assert_eq!(cgtoken.unbond(15), Ok(()));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(30);
+            assert_eq!(cgtoken.unbond(30), Ok(()));
+            assert_eq!(cgtoken.stake(5), Ok(()));
+
+            assert_eq!(cgtoken.num_checkpoints(accounts.alice), 3);
+            assert_eq!(
+                cgtoken.checkpoint(accounts.alice, 1),
+                Some(Checkpoint {
+                    timestamp: 20,
+                    value: 30,
+                })
+            );
+            assert_eq!(cgtoken.staked_balance_at(accounts.alice, 9), 0);
+            assert_eq!(cgtoken.staked_balance_at(accounts.alice, 10), 45);
+            assert_eq!(cgtoken.staked_balance_at(accounts.alice, 19), 45);
+            assert_eq!(cgtoken.staked_balance_at(accounts.alice, 20), 30);
+            assert_eq!(cgtoken.staked_balance_at(accounts.alice, 30), 5);
+            assert_eq!(cgtoken.staked_balance_at(accounts.alice, 1_000), 5);
+            assert_eq!(cgtoken.staked_balance_at(accounts.bob, 1_000), 0);
+        }
+
+        #[ink::test]
+        fn delegation_moves_voting_power() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(10);
+            assert_eq!(cgtoken.stake(40), Ok(()));
+            assert_eq!(cgtoken.voting_power_of(accounts.alice), 40);
+            assert_eq!(
+                cgtoken.delegate(accounts.alice),
+                Err(Error::InvalidDelegate)
+            );
+            assert_eq!(cgtoken.undelegate(), Err(Error::NotDelegated));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(20);
+            assert_eq!(cgtoken.delegate(accounts.bob), Ok(()));
+            assert_eq!(cgtoken.delegate_of(accounts.alice), Some(accounts.bob));
+            assert_eq!(cgtoken.voting_power_of(accounts.alice), 0);
+            assert_eq!(cgtoken.voting_power_of(accounts.bob), 40);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(30);
+            assert_eq!(cgtoken.unbond(10), Ok(()));
+            assert_eq!(cgtoken.voting_power_of(accounts.bob), 30);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(40);
+            assert_eq!(cgtoken.undelegate(), Ok(()));
+            assert_eq!(cgtoken.voting_power_of(accounts.alice), 30);
+            assert_eq!(cgtoken.voting_power_of(accounts.bob), 0);
+
+            assert_eq!(cgtoken.voting_power_at(accounts.alice, 15), 40);
+            assert_eq!(cgtoken.voting_power_at(accounts.alice, 25), 0);
+            assert_eq!(cgtoken.voting_power_at(accounts.bob, 25), 40);
+            assert_eq!(cgtoken.voting_power_at(accounts.bob, 35), 30);
+            assert_eq!(cgtoken.delegate_at(accounts.alice, 15), None);
+            assert_eq!(cgtoken.delegate_at(accounts.alice, 35), Some(accounts.bob));
+            assert_eq!(cgtoken.delegate_at(accounts.alice, 45), None);
+        }
+
+        #[ink::test]
+        fn delegated_power_is_not_passed_on() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut cgtoken = CgToken::new(100, 100, accounts.alice);
+
+            assert_eq!(cgtoken.transfer(accounts.bob, 50), Ok(()));
+            assert_eq!(cgtoken.stake(20), Ok(()));
+            assert_eq!(cgtoken.delegate(accounts.bob), Ok(()));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(cgtoken.stake(30), Ok(()));
+            assert_eq!(cgtoken.voting_power_of(accounts.bob), 50);
+            assert_eq!(cgtoken.delegate(accounts.charlie), Ok(()));
+            assert_eq!(cgtoken.voting_power_of(accounts.bob), 20);
+            assert_eq!(cgtoken.voting_power_of(accounts.charlie), 30);
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,44 @@
+//! The part of the PSP22 fungible token standard the platform relies on to
+//! hold bets and fees in tokens.
+//!
+//! Selectors and the error encoding follow the standard, so any PSP22 token
+//! can be called through this trait, not only `CgToken`.
+
+use ink::prelude::{string::String, vec::Vec};
+
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP22Error {
+    Custom(String),
+    InsufficientBalance,
+    InsufficientAllowance,
+    ZeroRecipientAddress,
+    ZeroSenderAddress,
+    SafeTransferCheckFailed(String),
+}
+
+#[ink::trait_definition]
+pub trait PSP22 {
+    /// Returns the balance held by `owner`.
+    #[ink(message)]
+    fn balance_of(&self, owner: ink::primitives::AccountId) -> u128;
+
+    /// Moves `value` from the caller to `to`.
+    #[ink(message)]
+    fn transfer(
+        &mut self,
+        to: ink::primitives::AccountId,
+        value: u128,
+        data: Vec<u8>,
+    ) -> Result<(), PSP22Error>;
+
+    /// Moves `value` from `from` to `to` out of the caller's allowance.
+    #[ink(message)]
+    fn transfer_from(
+        &mut self,
+        from: ink::primitives::AccountId,
+        to: ink::primitives::AccountId,
+        value: u128,
+        data: Vec<u8>,
+    ) -> Result<(), PSP22Error>;
+}
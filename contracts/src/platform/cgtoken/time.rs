@@ -0,0 +1,38 @@
+//! Time units for durations compared against `block_timestamp()`.
+//!
+//! ink! reports block timestamps in milliseconds since the Unix epoch, so
+//! every duration stored by the contracts is expressed in milliseconds too.
+
+pub const SECOND_MS: u64 = 1_000;
+pub const MINUTE_MS: u64 = 60 * SECOND_MS;
+pub const HOUR_MS: u64 = 60 * MINUTE_MS;
+pub const DAY_MS: u64 = 24 * HOUR_MS;
+
+pub const fn seconds(n: u64) -> u64 {
+    n * SECOND_MS
+}
+
+pub const fn minutes(n: u64) -> u64 {
+    n * MINUTE_MS
+}
+
+pub const fn hours(n: u64) -> u64 {
+    n * HOUR_MS
+}
+
+pub const fn days(n: u64) -> u64 {
+    n * DAY_MS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn units_are_milliseconds() {
+        assert_eq!(seconds(1), 1_000);
+        assert_eq!(minutes(1), 60_000);
+        assert_eq!(hours(1), 3_600_000);
+        assert_eq!(days(14), 14 * 24 * 60 * 60 * 1_000);
+    }
+}
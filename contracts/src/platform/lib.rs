@@ -1,16 +1,37 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
+pub use self::platorm::{Collateral, CredebilityGuardRef, Error, Metadata};
+
 #[ink::contract]
 mod platorm {
 
     use ink::{
-        //codegen::EmitEvent,
-        //reflect::ContractEventBase,
-        prelude::vec::Vec,
-        storage::Mapping,
+        codegen::{EmitEvent, TraitCallBuilder},
+        contract_ref,
+        env::{hash::Blake2x256, CallFlags},
+        prelude::{string::String, vec::Vec},
+        storage::{traits::StorageKey, Mapping},
+        ToAccountId,
     };
 
-    use cgtoken::CgTokenRef;
+    use cgtoken::{psp22::{PSP22Error, PSP22}, CgTokenRef};
+
+    // keeps claiming all positions of an account within one call's gas
+    const MAX_POSITIONS: usize = 32;
+    // implied probabilities and fee splits are in basis points
+    const PROBABILITY_PRECISION: u128 = 10_000;
+    const BASIS_POINTS: u16 = 10_000;
+    const MAX_FEE_TIERS: usize = 8;
+    // keeps a batch of claims within one block's weight
+    const MAX_BATCH_CLAIMS: usize = 32;
+    const MAX_RECIPIENTS: usize = 32;
+    // a CIDv1 of a sha2-256 multihash takes 36 bytes, leave room for
+    // longer digests
+    const MAX_CID_LEN: usize = 64;
+    const MAX_CONTENT_TYPE_LEN: usize = 64;
+    const MAX_URL_LEN: usize = 256;
+    // the length BCP 47 asks language tags to fit in
+    const MAX_LANGUAGE_LEN: usize = 35;
 
     #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
     #[cfg_attr(
@@ -25,6 +46,59 @@ mod platorm {
         direction: bool,
     }
 
+    // the bets an account placed on one news
+    type Positions = Vec<Bet>;
+
+    // voters can vote yes, no or uncertain
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum Cast {
+        Yes,
+        No,
+        Uncertain,
+    }
+
+    // bet fee discount for bettors with at least `min_staked` CgToken staked
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct FeeTier {
+        min_staked: u128,
+        // basis points taken off the fee
+        discount: u16,
+    }
+
+    // bet fees are `rate` basis points of the bet, at least `min_fee`, less
+    // the discount of the highest tier the bettor reaches
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct FeeSchedule {
+        min_fee: u128,
+        rate: u16,
+        // ordered by `min_staked`
+        tiers: Vec<FeeTier>,
+    }
+
+    // the asset a news is posted, bet on and paid out in
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum Collateral {
+        Native,
+        // a whitelisted PSP22 token such as `CgToken`
+        Token(AccountId),
+    }
+
     #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
     #[cfg_attr(
         feature = "std",
@@ -32,8 +106,43 @@ mod platorm {
     )]
     pub struct Vote {
         amount_staked: u128,
-        // voters can vote yes, no or uncertain and can change their opinion
-        cast: u8,
+        // voters can change their opinion until voting closes
+        cast: Cast,
+        // whether the voter took their share of the news' voter fees
+        rewarded: bool,
+    }
+
+    // how post and bet fees are shared, in basis points adding up to 10_000
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct FeeSplit {
+        treasury: u16,
+        author: u16,
+        // shared by the voters who backed the outcome
+        voters: u16,
+        // CgToken stakers
+        stakers: u16,
+    }
+
+    // where the article of a news is found and how to read it, the
+    // `news_cid` crate computes the CID of an article off-chain
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Metadata {
+        // binary CID of the article, naming the multihash of its content
+        pub cid: Vec<u8>,
+        // MIME type such as `text/html`
+        pub content_type: String,
+        // a gateway or the original location of the article
+        pub url: Option<String>,
+        // BCP 47 language tag such as `en` or `pt-BR`
+        pub language: String,
     }
 
     #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
@@ -43,116 +152,361 @@ mod platorm {
     )]
     pub struct News {
         author: AccountId,
+        // initial pool and every stake, less what has been paid out
         pool: u128,
         initial_pool: u128,
         claimed: bool,
+        // set by the first claim once voting ended, the outcome is final
+        settled: bool,
         posted_at: Timestamp,
         betting_until: Timestamp,
         voting_until: Timestamp,
         bets_yes_promised: u128,
         bets_no_promised: u128,
+        // sum of the stakes behind each side's positions
+        stakes_yes: u128,
+        stakes_no: u128,
+        // accounts holding positions
+        bettor_count: u32,
         votes_yes: u128,
         votes_uncertain: u128,
         votes_no: u128,
-        metadata: Hash,
+        metadata: Metadata,
+        collateral: Collateral,
+        // the voters' share of the fees paid on this news
+        voter_fees: u128,
+        // voter fees paid out so far, and the backing votes they went to
+        voter_fees_paid: u128,
+        rewarded_votes: u128,
+    }
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        // the pool moved and would promise less than the bettor accepted
+        PremiumBelowMinimum,
+        // the news pool could not pay the bet's side if it won
+        InsufficientCollateral,
+        // only the owner and the fee manager may withdraw treasury fees
+        NotFeeManager,
+        // more was asked than the treasury holds beyond the pools
+        InsufficientFees,
+        // the native transfer failed or the call to the token trapped, for
+        // instance when the token tried to call back into the platform
+        TransferFailed,
+        // the token refused the transfer
+        TokenTransferFailed(PSP22Error),
+        // reasons a news or its pool cannot be claimed
+        UnknownNews,
+        VotingNotEnded,
+        NothingToClaim,
+        NotAuthor,
+        AlreadyClaimed,
+        // the caller has no pending withdrawal or fees in the collateral
+        NothingToWithdraw,
+        // selling would pay out less than the seller accepted
+        PayoutBelowMinimum,
+        // only the owner may call the message
+        NotOwner,
+    }
+
+    // how the market currently prices a news, probabilities in basis points
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct MarketState {
+        implied_yes: u128,
+        implied_no: u128,
+        // pool left to promise before a side only gets its stake back
+        remaining_yes: u128,
+        remaining_no: u128,
+        staked_yes: u128,
+        staked_no: u128,
+        bettor_count: u32,
+    }
+
+    #[ink(event)]
+    pub struct VoteChanged {
+        #[ink(topic)]
+        id: u128,
+        #[ink(topic)]
+        voter: AccountId,
+        from_cast: Cast,
+        to_cast: Cast,
+        amount_staked: u128,
+    }
+
+    #[ink(event)]
+    pub struct PositionsTransferred {
+        #[ink(topic)]
+        id: u128,
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        positions: u32,
+    }
+
+    #[ink(event)]
+    pub struct PositionsSold {
+        #[ink(topic)]
+        id: u128,
+        #[ink(topic)]
+        seller: AccountId,
+        amount_promised: u128,
+        payout: u128,
+    }
+
+    #[ink(event)]
+    pub struct FeeSplitChanged {
+        fee_split: FeeSplit,
+    }
+
+    #[ink(event)]
+    pub struct FeeManagerChanged {
+        #[ink(topic)]
+        fee_manager: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct FeesWithdrawn {
+        #[ink(topic)]
+        to: AccountId,
+        collateral: Collateral,
+        amount: u128,
+    }
+
+    #[ink(event)]
+    pub struct AuthorFeesWithdrawn {
+        #[ink(topic)]
+        author: AccountId,
+        collateral: Collateral,
+        amount: u128,
+    }
+
+    #[ink(event)]
+    pub struct VoterRewardClaimed {
+        #[ink(topic)]
+        id: u128,
+        #[ink(topic)]
+        voter: AccountId,
+        amount: u128,
+    }
+
+    #[ink(event)]
+    pub struct StakerFeesDistributed {
+        collateral: Collateral,
+        amount: u128,
+    }
+
+    #[ink(event)]
+    pub struct PaymentDeferred {
+        #[ink(topic)]
+        to: AccountId,
+        collateral: Collateral,
+        amount: u128,
+    }
+
+    #[ink(event)]
+    pub struct PendingWithdrawn {
+        #[ink(topic)]
+        to: AccountId,
+        collateral: Collateral,
+        amount: u128,
     }
 
     #[ink(storage)]
     pub struct CredebilityGuard {
         version: u8,
         owner: AccountId,
+        // may withdraw treasury fees besides the owner
+        fee_manager: AccountId,
         post_fee: u128,
-        bet_fee: u128,
+        bet_fee: FeeSchedule,
+        // durations in milliseconds, like `block_timestamp()`
         betting_time: u64,
         voting_time: u64,
-        bettors: Mapping<(u128, AccountId), Bet>,
+        // every position an account opened on a news, in betting order
+        bettors: Mapping<(u128, AccountId), Positions>,
         voters: Mapping<(u128, AccountId), Vote>,
+        // stake that delegators of an account took back by voting themselves
+        delegation_overrides: Mapping<(u128, AccountId), u128>,
         counter: u128,
-        fees_containing: u128,
+        fee_split: FeeSplit,
+        // fees and pools are accounted separately for every collateral,
+        // `fees_containing` is the treasury's share of the fees
+        fees_containing: Mapping<Collateral, u128>,
+        author_fees: Mapping<(AccountId, Collateral), u128>,
+        staker_fees: Mapping<Collateral, u128>,
+        // author, voter and staker fees not paid out yet
+        reserved_fees: Mapping<Collateral, u128>,
+        // payouts whose transfer failed, kept for the receiver to `withdraw`
+        pending_withdrawals: Mapping<(AccountId, Collateral), u128>,
+        pending_containing: Mapping<Collateral, u128>,
+        // sum of the pools of all news, owed to their bettors and authors
+        pools_containing: Mapping<Collateral, u128>,
+        // what all pools may still have to pay their bettors, never above `pools_containing`
+        total_liabilities: Mapping<Collateral, u128>,
+        // tokens news can be posted in besides native value
+        collateral_tokens: Mapping<AccountId, ()>,
+        // the latest news posted for each article, by `metadata_hash`
+        metadata_index: Mapping<Hash, u128>,
         initial_pool: u128,
         news: Mapping<u128, News>,
         cgtoken: CgTokenRef,
     }
 
     impl CredebilityGuard {
-        /// Constructor that initializes the `bool` value to the given `init_value`.
+        /// Instantiates the platform together with its own `CgToken`.
+        /// Betting and voting durations are in milliseconds, see `cgtoken::time`.
         #[ink(constructor)]
         pub fn new(
             _version: u8,
             _post_fee: u128, 
             _bet_fee: u128,
-            _betting_time: u64,
-            _voting_time: u64,
+            _betting_time_ms: u64,
+            _voting_time_ms: u64,
             _inital_pool: u128,
             _cgtoken_code_hash: Hash,
         ) -> Self {
             let caller = Self::env().caller();
-            let max_supply = 100000000;
-            let cgtoken = CgTokenRef::new(max_supply)
+            // the rest of the cap is left for minters the owner appoints
+            let initial_supply = 100000000;
+            let max_supply = 1000000000;
+            let cgtoken = CgTokenRef::new(initial_supply, max_supply, caller)
                 .code_hash(_cgtoken_code_hash)
                 .endowment(0)
                 .salt_bytes([0xDE, 0xAD, 0xBE, 0xEF])
                 .instantiate();
+            Self::with_token(
+                _version,
+                _post_fee,
+                _bet_fee,
+                _betting_time_ms,
+                _voting_time_ms,
+                _inital_pool,
+                cgtoken,
+            )
+        }
+
+        // Sets up storage around an existing token, owned by the caller.
+        fn with_token(
+            version: u8,
+            post_fee: u128,
+            bet_fee: u128,
+            betting_time_ms: u64,
+            voting_time_ms: u64,
+            initial_pool: u128,
+            cgtoken: CgTokenRef,
+        ) -> Self {
+            // premiums are priced against the pool
+            assert!(initial_pool > 0, "initial pool must not be empty");
+            let mut collateral_tokens = Mapping::default();
+            collateral_tokens.insert(cgtoken.to_account_id(), &());
             Self {
-                version: _version,
-                owner: caller,
-                post_fee: _post_fee,
-                bet_fee: _bet_fee,
-                betting_time: _betting_time,
-                voting_time: _voting_time,
+                version,
+                owner: Self::env().caller(),
+                fee_manager: Self::env().caller(),
+                post_fee,
+                bet_fee: FeeSchedule {
+                    min_fee: bet_fee,
+                    rate: 0,
+                    tiers: Vec::new(),
+                },
+                betting_time: betting_time_ms,
+                voting_time: voting_time_ms,
                 counter: 0,
                 bettors: Mapping::default(),
                 voters: Mapping::default(),
-                fees_containing: 0,
-                initial_pool: _inital_pool,
+                delegation_overrides: Mapping::default(),
+                fee_split: FeeSplit {
+                    treasury: BASIS_POINTS,
+                    author: 0,
+                    voters: 0,
+                    stakers: 0,
+                },
+                fees_containing: Mapping::default(),
+                author_fees: Mapping::default(),
+                staker_fees: Mapping::default(),
+                reserved_fees: Mapping::default(),
+                pending_withdrawals: Mapping::default(),
+                pending_containing: Mapping::default(),
+                pools_containing: Mapping::default(),
+                total_liabilities: Mapping::default(),
+                collateral_tokens,
+                metadata_index: Mapping::default(),
+                initial_pool,
                 news: Mapping::default(),
-                cgtoken: cgtoken,
+                cgtoken,
             }
         }
 
+        /// Posts a news whose fee, pool, bets and payouts are all in
+        /// `collateral`. Native value is transferred with the call, tokens are
+        /// drawn from the caller's allowance for the platform.
         #[ink(message, payable)]
         pub fn post(
             &mut self,
-            _metadata: Hash,
+            metadata: Metadata,
+            collateral: Collateral,
         ) -> u128 {
             let caller = Self::env().caller();
             let current_timestamp = Self::env().block_timestamp();
-            let transferred_amount = self.env().transferred_value();
-            assert_eq!(transferred_amount, self.post_fee + self.initial_pool);
-            self.fees_containing += self.post_fee;
+            check_metadata(&metadata);
+            // bettors and voters of an article meet in one market at a time
+            let metadata_hash = self.metadata_hash(&metadata);
+            if let Some(existing) = self.find_by_metadata(metadata_hash) {
+                let existing = self.news.get(existing).unwrap_or_else(|| {
+                    panic!(
+                        "broken invariant: expected entry to exist"
+                    )
+                });
+                assert!(existing.voting_until < current_timestamp, "news already posted");
+            }
+            if let Collateral::Token(token) = collateral {
+                assert!(self.collateral_tokens.contains(token), "collateral not accepted");
+            }
+            self.collect(collateral, self.post_fee + self.initial_pool);
+            add_to(&mut self.pools_containing, collateral, self.initial_pool);
             self.counter += 1;
-            let news = News {
+            let mut news = News {
                 author: caller,
                 pool: self.initial_pool,
                 initial_pool: self.initial_pool,
                 claimed: false,
+                settled: false,
                 posted_at: current_timestamp,
                 betting_until: current_timestamp + self.betting_time,
                 voting_until: current_timestamp + self.betting_time + self.voting_time,
                 bets_yes_promised: 0,
                 bets_no_promised: 0,
+                stakes_yes: 0,
+                stakes_no: 0,
+                bettor_count: 0,
                 votes_yes: 0,
                 votes_uncertain: 0,
                 votes_no: 0,
-                metadata: _metadata,
+                metadata,
+                collateral,
+                voter_fees: 0,
+                voter_fees_paid: 0,
+                rewarded_votes: 0,
             };
+            self.share_fee(&mut news, self.post_fee);
             self.news.insert(self.counter, &news);
-            let bettor = Bet {
-                direction: true,
-                amount_promised: 0,
-                claimed: false,
-                amount_payed: self.initial_pool,
-            };
-            self.bettors.insert((self.counter, caller), &bettor);
-            return self.counter;
+            self.metadata_index.insert(metadata_hash, &self.counter);
+            self.counter
         }
 
+        /// Places a bet of `value` minus the bet fee in the news' collateral,
+        /// failing if it would be promised less than `min_premium`.
         #[ink(message, payable)]
         pub fn bet(
             &mut self,
             direction: bool,
             id: u128,
-        ) -> u128 {
+            min_premium: u128,
+            value: u128,
+        ) -> Result<u128, Error> {
             let caller = Self::env().caller();
             let current_timestamp = Self::env().block_timestamp();
             let mut news = self.news.get(id).unwrap_or_else(|| {
@@ -164,36 +518,99 @@ mod platorm {
                     "broken invariant: expected entry to exist"
                 )
         });
-        if let Some(_value) = self.bettors.get((id, caller)) {
-            panic!("account already bet");
-        }
-        // check if voting is open
-        assert!(news.betting_until < current_timestamp);
-        let transferred_amount = self.env().transferred_value();
-        assert!(transferred_amount > self.bet_fee);
-        self.fees_containing += self.bet_fee;
-        let amount = transferred_amount - self.bet_fee;
+        let mut positions = self.bettors.get((id, caller)).unwrap_or_default();
+        assert!(positions.len() < MAX_POSITIONS, "too many positions");
+        // check if betting is open
+        assert!(current_timestamp < news.betting_until);
+        let fee = self.get_bet_fee_for(caller, value);
+        assert!(value > fee);
+        let amount = value - fee;
         let premium = calculate_premium(amount, direction, news.pool, news.bets_yes_promised, news.bets_no_promised);
+        if premium < min_premium {
+            return Err(Error::PremiumBelowMinimum);
+        }
+        let owed_before = owed_to_bettors(&news);
+        news.pool += amount;
         if direction {
             news.bets_yes_promised += premium;
+            news.stakes_yes += amount;
         } else {
             news.bets_no_promised += premium;
+            news.stakes_no += amount;
+        }
+        if owed_to_bettors(&news) > news.pool {
+            return Err(Error::InsufficientCollateral);
+        }
+        self.collect(news.collateral, value);
+        shift(&mut self.total_liabilities, news.collateral, owed_before, owed_to_bettors(&news));
+        self.share_fee(&mut news, fee);
+        add_to(&mut self.pools_containing, news.collateral, amount);
+        if positions.is_empty() {
+            news.bettor_count += 1;
         }
         let bet = Bet {
             amount_payed: amount,
             amount_promised: premium,
             claimed: false,
-            direction: direction,
+            direction,
         };
+        positions.push(bet);
         self.news.insert(id, &news);
-        self.bettors.insert((id, caller), &bet);
-        return amount;
+        self.bettors.insert((id, caller), &positions);
+        Ok(amount)
+        }
+
+        /// Returns the premium a bet of `amount`, excluding the bet fee, would
+        /// be promised right now, and the probability in basis points the
+        /// price implies for its direction.
+        #[ink(message)]
+        pub fn quote_bet(
+            &self,
+            id: u128,
+            direction: bool,
+            amount: u128,
+        ) -> (u128, u128) {
+            let news = self.news.get(id).unwrap_or_else(|| {
+                panic!(
+                    "broken invariant: expected entry to exist"
+                )
+            });
+            // nothing to price, also once the author claimed the pool
+            if amount == 0 || news.pool == 0 {
+                return (0, 0);
+            }
+            let premium = calculate_premium(amount, direction, news.pool, news.bets_yes_promised, news.bets_no_promised);
+            (premium, amount * PROBABILITY_PRECISION / premium)
+        }
+
+        /// Returns how the market prices a news right now. The implied
+        /// probabilities are the price of a marginal bet on each side and add
+        /// up to slightly more than 100% because of the pool's margin.
+        #[ink(message)]
+        pub fn get_market_state(
+            &self,
+            id: u128,
+        ) -> MarketState {
+            let news = self.news.get(id).unwrap_or_else(|| {
+                panic!(
+                    "broken invariant: expected entry to exist"
+                )
+            });
+            MarketState {
+                implied_yes: implied_probability(true, news.pool, news.bets_yes_promised, news.bets_no_promised),
+                implied_no: implied_probability(false, news.pool, news.bets_yes_promised, news.bets_no_promised),
+                remaining_yes: news.pool.saturating_sub(news.bets_yes_promised),
+                remaining_no: news.pool.saturating_sub(news.bets_no_promised),
+                staked_yes: news.stakes_yes,
+                staked_no: news.stakes_no,
+                bettor_count: news.bettor_count,
+            }
         }
 
         #[ink(message)]
         pub fn vote(
             &mut self,
-            cast: u8,
+            cast: Cast,
             id: u128,
         ) -> u128 {
             let caller = Self::env().caller();
@@ -207,18 +624,6 @@ mod platorm {
                     "broken invariant: expected entry to exist for the caller"
                 )
             });
-            if cast == 0 {
-                news.votes_yes += 1;
-            } else if cast == 1 {
-                news.votes_no += 1; 
-            } else if cast == 2 {
-                news.votes_uncertain += 1;
-            }
-            else {
-                panic!(
-                    "illegal cast"
-                )
-            }
             // check if already voted
             if let Some(_value) = self.voters.get((id, caller)) {
                 panic!("account already voted");
@@ -226,218 +631,885 @@ mod platorm {
             // check if voting is open
             assert!(news.voting_until > current_timestamp);
             assert!(news.betting_until < current_timestamp);
-            let amount_staked = self.cgtoken.staked_balance_of(caller);
+            // voting power is fixed when betting closes, so staking after the
+            // fact does not help
+            let snapshot = news.betting_until;
+            let voting_power = self.cgtoken.voting_power_at(caller, snapshot);
+            let overridden = self.delegation_overrides.get((id, caller)).unwrap_or_default();
+            // voting directly overrides a delegation: the own stake is taken
+            // back from the delegate, even if they already voted
+            let delegate = self.cgtoken.delegate_at(caller, snapshot);
+            let own_stake = match delegate {
+                Some(_) => self.cgtoken.staked_balance_at(caller, snapshot),
+                None => 0,
+            };
+            let delegate_vote = delegate.and_then(|delegate| self.voters.get((id, delegate)));
+            let (amount_staked, taken_back) = override_delegation(
+                voting_power,
+                overridden,
+                own_stake,
+                delegate_vote.as_ref().map(|vote| vote.amount_staked),
+            );
+            assert!(amount_staked > 0, "no voting power");
+            if let Some(delegate) = delegate {
+                let overridden = self.delegation_overrides.get((id, delegate)).unwrap_or_default();
+                self.delegation_overrides.insert((id, delegate), &(overridden + own_stake));
+                if let Some(mut delegate_vote) = delegate_vote {
+                    *votes_for(&mut news, delegate_vote.cast) -= taken_back;
+                    delegate_vote.amount_staked -= taken_back;
+                    self.voters.insert((id, delegate), &delegate_vote);
+                }
+            }
+            *votes_for(&mut news, cast) += amount_staked;
             let vote = Vote {
-                amount_staked: amount_staked,
-                cast: cast,
+                amount_staked,
+                cast,
+                rewarded: false,
             };
             self.news.insert(id, &news);
             self.voters.insert((id, caller), &vote);
-            return amount_staked;
+            amount_staked
         }
 
+        /// Moves the caller's existing vote to another cast while voting is open.
         #[ink(message)]
-        pub fn claim(
+        pub fn change_vote(
             &mut self,
+            cast: Cast,
             id: u128,
         ) -> u128 {
-            // check if voting ended
             let caller = Self::env().caller();
             let current_timestamp = Self::env().block_timestamp();
-            let news = self.news.get(id).unwrap_or_else(|| {
-                // Contracts can also panic - this WILL fail and rollback the
-                // transaction. Caller can still handle it and
-                // recover but there will be no additional information about the error available. 
-                // Use when you know something *unexpected* happened.
+            let mut news = self.news.get(id).unwrap_or_else(|| {
                 panic!(
                     "broken invariant: expected entry to exist for the caller"
                 )
             });
-            let bettor = self.bettors.get((id, caller)).unwrap_or_else(|| {
-                // Contracts can also panic - this WILL fail and rollback the
-                // transaction. Caller can still handle it and
-                // recover but there will be no additional information about the error available. 
-                // Use when you know something *unexpected* happened.
-                panic!(
-                    "broken invariant: expected entry to exist for the caller"
-                )
+            let mut vote = self.voters.get((id, caller)).unwrap_or_else(|| {
+                panic!("account has not voted")
             });
-            assert_eq!(bettor.claimed, false);
-            assert!(news.voting_until < current_timestamp);
-            if news.votes_uncertain > news.votes_yes && news.votes_uncertain > news.votes_no {
-                let _result = self.env().transfer(caller, bettor.amount_payed);
-                return bettor.amount_promised;
-            } else if news.votes_yes > news.votes_no && bettor.direction == true {
-                let _result = self.env().transfer(caller, bettor.amount_payed);
-                return bettor.amount_promised;
-            } else if news.votes_yes < news.votes_no && bettor.direction == false {
-                let _result = self.env().transfer(caller, bettor.amount_payed);
-                return bettor.amount_promised;
-            } else if news.votes_yes > news.votes_no && bettor.direction == false {
-                return 0;
-            } else if news.votes_yes < news.votes_no && bettor.direction == true {
-                return 0;
-            } else {
-                let _result = self.env().transfer(caller, bettor.amount_payed);
-                return bettor.amount_promised;
-            }
+            // check if voting is open
+            assert!(news.voting_until > current_timestamp);
+            assert!(news.betting_until < current_timestamp);
+            *votes_for(&mut news, vote.cast) -= vote.amount_staked;
+            *votes_for(&mut news, cast) += vote.amount_staked;
+            EmitEvent::<Self>::emit_event(self.env(), VoteChanged {
+                id,
+                voter: caller,
+                from_cast: vote.cast,
+                to_cast: cast,
+                amount_staked: vote.amount_staked,
+            });
+            vote.cast = cast;
+            self.news.insert(id, &news);
+            self.voters.insert((id, caller), &vote);
+            vote.amount_staked
         }
 
         #[ink(message)]
-        pub fn pool_claim(
+        pub fn claim(
             &mut self,
             id: u128,
-        ) -> u128 {
+        ) -> Result<u128, Error> {
             let caller = Self::env().caller();
-            let current_timestamp = Self::env().block_timestamp();
-            let mut news = self.news.get(id).unwrap_or_else(|| {
-                // Contracts can also panic - this WILL fail and rollback the
-                // transaction. Caller can still handle it and
-                // recover but there will be no additional information about the error available. 
-                // Use when you know something *unexpected* happened.
-                panic!(
-                    "broken invariant: expected entry to exist for the caller"
-                )
-            });
-            assert_eq!(news.author, caller);
-            assert!(current_timestamp > news.voting_until);
-            assert_eq!(news.claimed, false);
-            let mut _payout = 0;
-            if news.votes_uncertain > news.votes_yes && news.votes_uncertain > news.votes_no {
-                _payout = news.initial_pool;
-            } else if news.votes_yes > news.votes_no {
-                _payout = news.pool - news.bets_yes_promised;
-            } else if news.votes_yes < news.votes_no {
-                _payout = news.pool - news.bets_no_promised;
-            } else {
-                _payout = news.initial_pool;
-            }
-            news.claimed = true;
-            self.news.insert(id, &news);
-            let _result = self.env().transfer(caller, _payout);
-            return _payout;
+            let (news, positions) = self.claimable(id, caller)?;
+            Ok(self.claim_positions(id, caller, news, positions))
         }
 
+        /// Claims the caller's positions on every news in `ids` that they
+        /// can claim, skipping the others. Returns the payout or the reason
+        /// a news was skipped, in the order of `ids`.
         #[ink(message)]
-        pub fn fee_payout(
+        pub fn claim_many(
             &mut self,
-        ) -> u128 {
-            assert!(self.fees_containing > 0);
-            let _fees_containing = self.fees_containing;
-            let _result = self.env().transfer(self.owner, _fees_containing);
-            self.fees_containing = 0;
-            return _fees_containing;
+            ids: Vec<u128>,
+        ) -> Vec<Result<u128, Error>> {
+            assert!(ids.len() <= MAX_BATCH_CLAIMS, "too many ids");
+            let caller = Self::env().caller();
+            ids.into_iter().map(|id| {
+                let (news, positions) = self.claimable(id, caller)?;
+                Ok(self.claim_positions(id, caller, news, positions))
+            }).collect()
         }
 
+        // Returns the news and the positions of `caller` on it if voting
+        // ended and some of them are unclaimed, or why they cannot be
+        // claimed.
+        fn claimable(&self, id: u128, caller: AccountId) -> Result<(News, Positions), Error> {
+            let news = self.news.get(id).ok_or(Error::UnknownNews)?;
+            let positions = self.bettors.get((id, caller)).unwrap_or_default();
+            if !positions.iter().any(|bet| !bet.claimed) {
+                return Err(Error::NothingToClaim)
+            }
+            if news.voting_until >= Self::env().block_timestamp() {
+                return Err(Error::VotingNotEnded)
+            }
+            Ok((news, positions))
+        }
 
-        #[ink(message)]
-        pub fn get_version(&self) -> u8 {
-            return self.version;
+        // Settles the news and pays out the unclaimed positions of `caller`,
+        // once `claimable` checked they can be claimed.
+        fn claim_positions(&mut self, id: u128, caller: AccountId, mut news: News, mut positions: Positions) -> u128 {
+            let uncertain = news.votes_uncertain > news.votes_yes && news.votes_uncertain > news.votes_no;
+            let tie = news.votes_yes == news.votes_no;
+            let owed_before = owed_to_bettors(&news);
+            news.settled = true;
+            let mut payout = 0;
+            for bet in positions.iter_mut().filter(|bet| !bet.claimed) {
+                // an uncertain outcome or a tie refunds the stake
+                if uncertain || tie {
+                    payout += bet.amount_payed;
+                } else if bet.direction == (news.votes_yes > news.votes_no) {
+                    payout += bet.amount_promised;
+                }
+                // settled positions are no longer owed by the pool
+                if bet.direction {
+                    news.bets_yes_promised -= bet.amount_promised;
+                    news.stakes_yes -= bet.amount_payed;
+                } else {
+                    news.bets_no_promised -= bet.amount_promised;
+                    news.stakes_no -= bet.amount_payed;
+                }
+                bet.claimed = true;
+            }
+            news.pool -= payout;
+            take_from(&mut self.pools_containing, news.collateral, payout);
+            shift(&mut self.total_liabilities, news.collateral, owed_before, owed_to_bettors(&news));
+            self.news.insert(id, &news);
+            self.bettors.insert((id, caller), &positions);
+            self.pay_or_credit(news.collateral, caller, payout);
+            payout
         }
 
+        /// Returns the positions an account holds on a news.
         #[ink(message)]
-        pub fn get_owner(&self) -> AccountId {
-            return self.owner;
+        pub fn get_positions(
+            &self,
+            id: u128,
+            account: AccountId,
+        ) -> Positions {
+            self.bettors.get((id, account)).unwrap_or_default()
         }
 
+        /// Returns a news with its current tallies, if it exists.
         #[ink(message)]
-        pub fn get_post_fee(&self) -> u128 {
-            return self.post_fee;
+        pub fn get_news(
+            &self,
+            id: u128,
+        ) -> Option<News> {
+            self.news.get(id)
         }
 
+        /// Hands all of the caller's positions on a news to another account.
+        /// Possible until voting closes, the receiver claims them instead.
         #[ink(message)]
-        pub fn get_bet_fee(&self) -> u128 {
-            return self.bet_fee;
+        pub fn transfer_bet(
+            &mut self,
+            id: u128,
+            to: AccountId,
+        ) -> u32 {
+            let caller = Self::env().caller();
+            let current_timestamp = Self::env().block_timestamp();
+            let mut news = self.news.get(id).unwrap_or_else(|| {
+                panic!(
+                    "broken invariant: expected entry to exist"
+                )
+            });
+            assert!(current_timestamp < news.voting_until);
+            assert_ne!(caller, to);
+            let mut positions = self.bettors.take((id, caller)).unwrap_or_default();
+            assert!(!positions.is_empty(), "account has no positions");
+            let transferred = positions.len() as u32;
+            let mut receiving = self.bettors.get((id, to)).unwrap_or_default();
+            if !receiving.is_empty() {
+                news.bettor_count -= 1;
+                self.news.insert(id, &news);
+            }
+            receiving.append(&mut positions);
+            assert!(receiving.len() <= MAX_POSITIONS, "too many positions");
+            self.bettors.insert((id, to), &receiving);
+            EmitEvent::<Self>::emit_event(self.env(), PositionsTransferred {
+                id,
+                from: caller,
+                to,
+                positions: transferred,
+            });
+            transferred
         }
 
+        /// Sells all of the caller's positions on a news back to its pool
+        /// while betting is open. The pool buys each position at the stake
+        /// that would be promised the same premium right now, and takes over
+        /// the stake the position was bought with.
         #[ink(message)]
-        pub fn get_betting_time(&self) -> u64 {
-            return self.betting_time;
+        pub fn sell_position(
+            &mut self,
+            id: u128,
+            min_out: u128,
+        ) -> Result<u128, Error> {
+            let caller = Self::env().caller();
+            let current_timestamp = Self::env().block_timestamp();
+            let mut news = self.news.get(id).unwrap_or_else(|| {
+                panic!(
+                    "broken invariant: expected entry to exist"
+                )
+            });
+            assert!(current_timestamp < news.betting_until);
+            let positions = self.bettors.get((id, caller)).unwrap_or_default();
+            assert!(!positions.is_empty(), "account has no positions");
+            let owed_before = owed_to_bettors(&news);
+            let mut amount_promised = 0;
+            let mut payout = 0;
+            for bet in positions.iter() {
+                // priced against the pool as it was without the position
+                let exit_value = calculate_exit_value(
+                    bet.amount_promised,
+                    bet.direction,
+                    news.pool - bet.amount_payed,
+                    news.bets_yes_promised,
+                    news.bets_no_promised,
+                );
+                if bet.direction {
+                    news.bets_yes_promised -= bet.amount_promised;
+                    news.stakes_yes -= bet.amount_payed;
+                } else {
+                    news.bets_no_promised -= bet.amount_promised;
+                    news.stakes_no -= bet.amount_payed;
+                }
+                news.pool -= exit_value;
+                amount_promised += bet.amount_promised;
+                payout += exit_value;
+            }
+            if payout < min_out {
+                return Err(Error::PayoutBelowMinimum);
+            }
+            if owed_to_bettors(&news) > news.pool {
+                return Err(Error::InsufficientCollateral);
+            }
+            self.bettors.remove((id, caller));
+            news.bettor_count -= 1;
+            take_from(&mut self.pools_containing, news.collateral, payout);
+            shift(&mut self.total_liabilities, news.collateral, owed_before, owed_to_bettors(&news));
+            self.news.insert(id, &news);
+            self.pay_or_credit(news.collateral, caller, payout);
+            EmitEvent::<Self>::emit_event(self.env(), PositionsSold {
+                id,
+                seller: caller,
+                amount_promised,
+                payout,
+            });
+            Ok(payout)
         }
 
         #[ink(message)]
-        pub fn get_voting_time(&self) -> u64 {
-            return self.voting_time;
+        pub fn pool_claim(
+            &mut self,
+            id: u128,
+        ) -> Result<u128, Error> {
+            let caller = Self::env().caller();
+            let news = self.pool_claimable(id, caller)?;
+            Ok(self.claim_pool(id, caller, news))
         }
 
+        /// Claims the pool of every news in `ids` the caller authored and can
+        /// claim, skipping the others. Returns the payout or the reason a
+        /// news was skipped, in the order of `ids`.
         #[ink(message)]
-        pub fn get_counter(&self) -> u128 {
-            return self.counter;
+        pub fn pool_claim_many(
+            &mut self,
+            ids: Vec<u128>,
+        ) -> Vec<Result<u128, Error>> {
+            assert!(ids.len() <= MAX_BATCH_CLAIMS, "too many ids");
+            let caller = Self::env().caller();
+            ids.into_iter().map(|id| {
+                let news = self.pool_claimable(id, caller)?;
+                Ok(self.claim_pool(id, caller, news))
+            }).collect()
         }
 
-        #[ink(message)]
-        pub fn get_fees_containing(&self) -> u128 {
-            return self.fees_containing;
+        // Returns the news if `caller` authored it, voting ended and its pool
+        // is unclaimed, or why the pool cannot be claimed.
+        fn pool_claimable(&self, id: u128, caller: AccountId) -> Result<News, Error> {
+            let news = self.news.get(id).ok_or(Error::UnknownNews)?;
+            if news.author != caller {
+                return Err(Error::NotAuthor)
+            }
+            if news.voting_until >= Self::env().block_timestamp() {
+                return Err(Error::VotingNotEnded)
+            }
+            if news.claimed {
+                return Err(Error::AlreadyClaimed)
+            }
+            Ok(news)
         }
 
-        #[ink(message)]
-        pub fn get_initial_pool(&self) -> u128 {
-            return self.initial_pool;
+        // Settles the news and pays its author what bettors are not owed,
+        // once `pool_claimable` checked it can be claimed.
+        fn claim_pool(&mut self, id: u128, caller: AccountId, mut news: News) -> u128 {
+            // the author gets what is left after what bettors are still owed
+            let owed_before = owed_to_bettors(&news);
+            news.claimed = true;
+            news.settled = true;
+            // nobody can claim the voter fees if no vote backed the outcome
+            if backing_votes(&news) == 0 && news.voter_fees > 0 {
+                take_from(&mut self.reserved_fees, news.collateral, news.voter_fees);
+                add_to(&mut self.fees_containing, news.collateral, news.voter_fees);
+                news.voter_fees = 0;
+            }
+            let _payout = news.pool - owed_to_bettors(&news);
+            news.pool -= _payout;
+            take_from(&mut self.pools_containing, news.collateral, _payout);
+            shift(&mut self.total_liabilities, news.collateral, owed_before, owed_to_bettors(&news));
+            self.news.insert(id, &news);
+            self.pay_or_credit(news.collateral, caller, _payout);
+            _payout
         }
 
+        /// Sends `amount` of the treasury fees in `collateral` to `to`. Only
+        /// what the balance holds beyond the pools and the other shares of
+        /// the fees can be withdrawn, and the fees are kept if the transfer
+        /// fails.
         #[ink(message)]
-        pub fn get_all_news(&self) -> Vec<News> {
-            let mut news_list = Vec::<News>::default();
-            for n in 0..self.counter {
-                let news: News = self.news.get(n).unwrap();
-                news_list.push(news);
+        pub fn withdraw_fees(
+            &mut self,
+            collateral: Collateral,
+            to: AccountId,
+            amount: u128,
+        ) -> Result<(), Error> {
+            let caller = Self::env().caller();
+            if caller != self.owner && caller != self.fee_manager {
+                return Err(Error::NotFeeManager)
+            }
+            let held = self.get_pools_containing(collateral)
+                + self.get_reserved_fees(collateral)
+                + self.get_pending_containing(collateral);
+            let surplus = self.balance_in(collateral).saturating_sub(held);
+            if amount > self.get_fees_containing(collateral).min(surplus) {
+                return Err(Error::InsufficientFees)
             }
-            return news_list;
+            // an error reverts the withdrawal, the fees stay with the treasury
+            take_from(&mut self.fees_containing, collateral, amount);
+            self.pay(collateral, to, amount)?;
+            EmitEvent::<Self>::emit_event(self.env(), FeesWithdrawn {
+                to,
+                collateral,
+                amount,
+            });
+            Ok(())
         }
 
+        /// Pays the caller the author share of fees collected on their news.
         #[ink(message)]
-        pub fn get_token(&self) -> CgTokenRef {
-            return self.cgtoken.clone();
+        pub fn withdraw_author_fees(
+            &mut self,
+            collateral: Collateral,
+        ) -> Result<u128, Error> {
+            let caller = Self::env().caller();
+            let amount = self.get_author_fees(caller, collateral);
+            if amount == 0 {
+                return Err(Error::NothingToWithdraw);
+            }
+            self.author_fees.remove((caller, collateral));
+            take_from(&mut self.reserved_fees, collateral, amount);
+            self.pay_or_credit(collateral, caller, amount);
+            EmitEvent::<Self>::emit_event(self.env(), AuthorFeesWithdrawn {
+                author: caller,
+                collateral,
+                amount,
+            });
+            Ok(amount)
         }
 
+        /// Pays the caller their part of the voter fees of a news, shared
+        /// by stake among the votes that backed the outcome. What the shares
+        /// leave over from rounding goes to the treasury with the last one.
         #[ink(message)]
-        pub fn set_owner(
-            & mut self,
-            address: AccountId
-        ) -> AccountId {
-            assert_eq!(self.owner, Self::env().caller());
-            self.owner = address;
-            return address;
+        pub fn claim_voter_reward(
+            &mut self,
+            id: u128,
+        ) -> Result<u128, Error> {
+            let caller = Self::env().caller();
+            let current_timestamp = Self::env().block_timestamp();
+            let mut news = self.news.get(id).ok_or(Error::UnknownNews)?;
+            let mut vote = self.voters.get((id, caller)).ok_or(Error::NothingToClaim)?;
+            if news.voting_until >= current_timestamp {
+                return Err(Error::VotingNotEnded);
+            }
+            if vote.rewarded {
+                return Err(Error::AlreadyClaimed);
+            }
+            let backing = backing_votes(&news);
+            if !backed_outcome(&news, vote.cast) || backing == 0 {
+                return Err(Error::NothingToClaim);
+            }
+            let amount = news.voter_fees * vote.amount_staked / backing;
+            vote.rewarded = true;
+            self.voters.insert((id, caller), &vote);
+            take_from(&mut self.reserved_fees, news.collateral, amount);
+            news.voter_fees_paid += amount;
+            news.rewarded_votes += vote.amount_staked;
+            if news.rewarded_votes == backing {
+                let dust = news.voter_fees - news.voter_fees_paid;
+                take_from(&mut self.reserved_fees, news.collateral, dust);
+                add_to(&mut self.fees_containing, news.collateral, dust);
+                news.voter_fees = news.voter_fees_paid;
+            }
+            self.news.insert(id, &news);
+            self.pay_or_credit(news.collateral, caller, amount);
+            EmitEvent::<Self>::emit_event(self.env(), VoterRewardClaimed {
+                id,
+                voter: caller,
+                amount,
+            });
+            Ok(amount)
         }
 
+        /// Hands the staker share of the fees in CgToken to its stakers, as
+        /// rewards. News in other assets leave no staker fees to distribute.
         #[ink(message)]
-        pub fn set_post_fee(
+        pub fn distribute_staker_fees(
             &mut self,
-            post_fee: u128
-        ) -> u128 {
-            assert_eq!(self.owner, Self::env().caller());
-            self.post_fee = post_fee;
-            return post_fee;
+            collateral: Collateral,
+        ) -> Result<u128, Error> {
+            if self.owner != Self::env().caller() {
+                return Err(Error::NotOwner);
+            }
+            let amount = self.get_staker_fees(collateral);
+            if amount == 0 {
+                return Err(Error::NothingToWithdraw);
+            }
+            self.staker_fees.remove(collateral);
+            take_from(&mut self.reserved_fees, collateral, amount);
+            assert!(self.cgtoken.distribute_rewards(amount).is_ok());
+            EmitEvent::<Self>::emit_event(self.env(), StakerFeesDistributed {
+                collateral,
+                amount,
+            });
+            Ok(amount)
         }
 
+        /// Pays the caller what earlier payouts in `collateral` could not
+        /// transfer to them. The amount stays pending if the transfer fails
+        /// again.
         #[ink(message)]
-        pub fn set_bet_fee(
+        pub fn withdraw(
             &mut self,
-            bet_fee: u128
-        ) -> u128 {
-            assert_eq!(self.owner, Self::env().caller());
-            self.bet_fee = bet_fee;
-            return bet_fee;
+            collateral: Collateral,
+        ) -> Result<u128, Error> {
+            let caller = Self::env().caller();
+            let amount = self.get_pending_withdrawal(caller, collateral);
+            if amount == 0 {
+                return Err(Error::NothingToWithdraw);
+            }
+            self.pending_withdrawals.remove((caller, collateral));
+            take_from(&mut self.pending_containing, collateral, amount);
+            self.pay(collateral, caller, amount)?;
+            EmitEvent::<Self>::emit_event(self.env(), PendingWithdrawn {
+                to: caller,
+                collateral,
+                amount,
+            });
+            Ok(amount)
         }
 
+        /// Returns whether the contract's balance in `collateral` covers
+        /// everything it holds for others: the pools of all news, the
+        /// collected fees and the pending withdrawals.
         #[ink(message)]
-        pub fn set_betting_time(
-            &mut self,
-            betting_time: u64,
+        pub fn check_invariant(&self, collateral: Collateral) -> bool {
+            let pools_containing = self.get_pools_containing(collateral);
+            let fees = self.get_fees_containing(collateral) + self.get_reserved_fees(collateral);
+            let pending = self.get_pending_containing(collateral);
+            self.get_total_liabilities(collateral) <= pools_containing
+                && self.balance_in(collateral) >= pools_containing + fees + pending
+        }
+
+        // Shares a fee paid on a news between the treasury, the author, the
+        // news' voters and the CgToken stakers. Only fees in CgToken can
+        // reach the stakers, the staker share of fees in other assets goes
+        // to the treasury.
+        fn share_fee(&mut self, news: &mut News, fee: u128) {
+            let (mut treasury, author, voters, mut stakers) = split_fee(fee, &self.fee_split);
+            if news.collateral != Collateral::Token(self.cgtoken.to_account_id()) {
+                treasury += stakers;
+                stakers = 0;
+            }
+            add_to(&mut self.fees_containing, news.collateral, treasury);
+            let author_fees = self.get_author_fees(news.author, news.collateral);
+            self.author_fees.insert((news.author, news.collateral), &(author_fees + author));
+            news.voter_fees += voters;
+            add_to(&mut self.staker_fees, news.collateral, stakers);
+            add_to(&mut self.reserved_fees, news.collateral, author + voters + stakers);
+        }
+
+        // Takes `value` from the caller: native value must come with the
+        // call, tokens are drawn from the caller's allowance.
+        fn collect(&self, collateral: Collateral, value: u128) {
+            match collateral {
+                Collateral::Native => {
+                    assert_eq!(self.env().transferred_value(), value);
+                }
+                Collateral::Token(token) => {
+                    assert_eq!(self.env().transferred_value(), 0);
+                    let mut token: contract_ref!(PSP22) = token.into();
+                    let transferred = token.transfer_from(self.env().caller(), self.env().account_id(), value, Vec::new());
+                    assert!(transferred.is_ok(), "collateral transfer failed");
+                }
+            }
+        }
+
+        // Sends `value` to `to`. Messages call it only after all their
+        // storage is written, and tokens may not call back into the platform
+        // while they are paid out of it.
+        fn pay(&self, collateral: Collateral, to: AccountId, value: u128) -> Result<(), Error> {
+            match collateral {
+                Collateral::Native => {
+                    self.env().transfer(to, value).map_err(|_| Error::TransferFailed)
+                }
+                Collateral::Token(token) => {
+                    let mut token: contract_ref!(PSP22) = token.into();
+                    match token
+                        .call_mut()
+                        .transfer(to, value, Vec::new())
+                        .call_flags(CallFlags::default().set_allow_reentry(false))
+                        .try_invoke()
+                    {
+                        Ok(Ok(result)) => result.map_err(Error::TokenTransferFailed),
+                        _ => Err(Error::TransferFailed),
+                    }
+                }
+            }
+        }
+
+        // Pays like `pay`, but keeps the payout for the receiver to
+        // `withdraw` when the transfer fails, so that a receiver that cannot
+        // take it does not block the message paying out.
+        fn pay_or_credit(&mut self, collateral: Collateral, to: AccountId, value: u128) {
+            if value == 0 || self.pay(collateral, to, value).is_ok() {
+                return
+            }
+            let pending = self.get_pending_withdrawal(to, collateral);
+            self.pending_withdrawals.insert((to, collateral), &(pending + value));
+            add_to(&mut self.pending_containing, collateral, value);
+            EmitEvent::<Self>::emit_event(self.env(), PaymentDeferred {
+                to,
+                collateral,
+                amount: value,
+            });
+        }
+
+        fn balance_in(&self, collateral: Collateral) -> u128 {
+            match collateral {
+                Collateral::Native => self.env().balance(),
+                Collateral::Token(token) => {
+                    let token: contract_ref!(PSP22) = token.into();
+                    token.balance_of(self.env().account_id())
+                }
+            }
+        }
+
+        // CgToken held beyond what backs pools, fees and pending withdrawals
+        // in it, the only part the owner may hand out
+        fn spendable_cgtoken(&self) -> u128 {
+            let collateral = Collateral::Token(self.cgtoken.to_account_id());
+            let held = self.get_pools_containing(collateral)
+                + self.get_reserved_fees(collateral)
+                + self.get_fees_containing(collateral)
+                + self.get_pending_containing(collateral);
+            self.balance_in(collateral).saturating_sub(held)
+        }
+
+        /// Hands out CgToken held by the platform, e.g. the initial supply.
+        /// CgToken backing news posted in it cannot be handed out.
+        #[ink(message)]
+        pub fn distribute_tokens(
+            &mut self,
+            recipients: Vec<(AccountId, u128)>,
+        ) -> u128 {
+            assert_eq!(self.owner, Self::env().caller());
+            assert!(recipients.len() <= MAX_RECIPIENTS, "too many recipients");
+            let total: u128 = recipients.iter().map(|(_, amount)| amount).sum();
+            assert!(total <= self.spendable_cgtoken(), "CgToken is held as collateral");
+            let mut distributed = 0;
+            for (recipient, amount) in recipients {
+                assert!(self.cgtoken.transfer(recipient, amount).is_ok());
+                distributed += amount;
+            }
+            distributed
+        }
+
+        /// Pushes `amount` of the platform's own CgToken to the current
+        /// stakers, like `distribute_tokens` only out of what is spendable.
+        #[ink(message)]
+        pub fn distribute_staker_rewards(
+            &mut self,
+            amount: u128,
+        ) -> u128 {
+            assert_eq!(self.owner, Self::env().caller());
+            assert!(amount <= self.spendable_cgtoken(), "CgToken is held as collateral");
+            assert!(self.cgtoken.distribute_rewards(amount).is_ok());
+            amount
+        }
+
+
+        #[ink(message)]
+        pub fn get_version(&self) -> u8 {
+            self.version
+        }
+
+        #[ink(message)]
+        pub fn get_owner(&self) -> AccountId {
+            self.owner
+        }
+
+        #[ink(message)]
+        pub fn get_fee_manager(&self) -> AccountId {
+            self.fee_manager
+        }
+
+        #[ink(message)]
+        pub fn get_post_fee(&self) -> u128 {
+            self.post_fee
+        }
+
+        #[ink(message)]
+        pub fn get_bet_fee(&self) -> FeeSchedule {
+            self.bet_fee.clone()
+        }
+
+        /// Returns the fee `bettor` would pay on a bet of `value`.
+        #[ink(message)]
+        pub fn get_bet_fee_for(&self, bettor: AccountId, value: u128) -> u128 {
+            // only look up the stake if it can earn a discount
+            let staked = if self.bet_fee.tiers.is_empty() {
+                0
+            } else {
+                self.cgtoken.staked_balance_of(bettor)
+            };
+            calculate_fee(value, staked, &self.bet_fee)
+        }
+
+        /// Betting duration in milliseconds.
+        #[ink(message)]
+        pub fn get_betting_time(&self) -> u64 {
+            self.betting_time
+        }
+
+        /// Voting duration in milliseconds.
+        #[ink(message)]
+        pub fn get_voting_time(&self) -> u64 {
+            self.voting_time
+        }
+
+        #[ink(message)]
+        pub fn get_counter(&self) -> u128 {
+            self.counter
+        }
+
+        #[ink(message)]
+        pub fn get_fees_containing(&self, collateral: Collateral) -> u128 {
+            self.fees_containing.get(collateral).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn get_fee_split(&self) -> FeeSplit {
+            self.fee_split
+        }
+
+        #[ink(message)]
+        pub fn get_author_fees(&self, author: AccountId, collateral: Collateral) -> u128 {
+            self.author_fees.get((author, collateral)).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn get_staker_fees(&self, collateral: Collateral) -> u128 {
+            self.staker_fees.get(collateral).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn get_reserved_fees(&self, collateral: Collateral) -> u128 {
+            self.reserved_fees.get(collateral).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn get_pending_withdrawal(&self, account: AccountId, collateral: Collateral) -> u128 {
+            self.pending_withdrawals.get((account, collateral)).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn get_pending_containing(&self, collateral: Collateral) -> u128 {
+            self.pending_containing.get(collateral).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn get_pools_containing(&self, collateral: Collateral) -> u128 {
+            self.pools_containing.get(collateral).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn get_total_liabilities(&self, collateral: Collateral) -> u128 {
+            self.total_liabilities.get(collateral).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn is_collateral_token(&self, token: AccountId) -> bool {
+            self.collateral_tokens.contains(token)
+        }
+
+        #[ink(message)]
+        pub fn get_initial_pool(&self) -> u128 {
+            self.initial_pool
+        }
+
+        /// Returns the latest news posted for the article whose CID hashes
+        /// to `hash` with Blake2x256, whether or not its market is still open.
+        #[ink(message)]
+        pub fn find_by_metadata(&self, hash: Hash) -> Option<u128> {
+            self.metadata_index.get(hash)
+        }
+
+        // News are indexed by their CID alone, so an article posted again
+        // under another URL or content type is still found.
+        fn metadata_hash(&self, metadata: &Metadata) -> Hash {
+            Hash::from(self.env().hash_bytes::<Blake2x256>(&metadata.cid))
+        }
+
+        #[ink(message)]
+        pub fn get_all_news(&self) -> Vec<News> {
+            let mut news_list = Vec::<News>::default();
+            for n in 0..self.counter {
+                let news: News = self.news.get(n).unwrap();
+                news_list.push(news);
+            }
+            news_list
+        }
+
+        #[ink(message)]
+        pub fn get_token(&self) -> CgTokenRef {
+            self.cgtoken.clone()
+        }
+
+        #[ink(message)]
+        pub fn set_owner(
+            & mut self,
+            address: AccountId
+        ) -> AccountId {
+            assert_eq!(self.owner, Self::env().caller());
+            self.owner = address;
+            address
+        }
+
+        /// Lets `address` withdraw treasury fees besides the owner.
+        #[ink(message)]
+        pub fn set_fee_manager(
+            & mut self,
+            address: AccountId
+        ) -> AccountId {
+            assert_eq!(self.owner, Self::env().caller());
+            self.fee_manager = address;
+            EmitEvent::<Self>::emit_event(self.env(), FeeManagerChanged {
+                fee_manager: address,
+            });
+            address
+        }
+
+        /// Allows or disallows posting new news in a PSP22 token. News already
+        /// posted in it keep being paid out in it.
+        #[ink(message)]
+        pub fn set_collateral_token(
+            &mut self,
+            token: AccountId,
+            accepted: bool,
+        ) -> bool {
+            assert_eq!(self.owner, Self::env().caller());
+            if accepted {
+                self.collateral_tokens.insert(token, &());
+            } else {
+                self.collateral_tokens.remove(token);
+            }
+            accepted
+        }
+
+        /// Sets how post and bet fees are shared, in basis points that have to
+        /// add up to 10_000.
+        #[ink(message)]
+        pub fn set_fee_split(
+            &mut self,
+            treasury: u16,
+            author: u16,
+            voters: u16,
+            stakers: u16,
+        ) -> FeeSplit {
+            assert_eq!(self.owner, Self::env().caller());
+            assert_eq!(
+                treasury as u32 + author as u32 + voters as u32 + stakers as u32,
+                BASIS_POINTS as u32,
+                "fee split must add up to 10_000 basis points"
+            );
+            self.fee_split = FeeSplit {
+                treasury,
+                author,
+                voters,
+                stakers,
+            };
+            EmitEvent::<Self>::emit_event(self.env(), FeeSplitChanged {
+                fee_split: self.fee_split,
+            });
+            self.fee_split
+        }
+
+        #[ink(message)]
+        pub fn set_post_fee(
+            &mut self,
+            post_fee: u128
+        ) -> u128 {
+            assert_eq!(self.owner, Self::env().caller());
+            self.post_fee = post_fee;
+            post_fee
+        }
+
+        /// Sets the bet fee schedule: a rate in basis points of the bet with a
+        /// minimum, and discount tiers ordered by the stake they require.
+        #[ink(message)]
+        pub fn set_bet_fee(
+            &mut self,
+            min_fee: u128,
+            rate: u16,
+            tiers: Vec<FeeTier>,
+        ) -> FeeSchedule {
+            assert_eq!(self.owner, Self::env().caller());
+            assert!(rate <= BASIS_POINTS);
+            assert!(tiers.len() <= MAX_FEE_TIERS, "too many fee tiers");
+            assert!(tiers.iter().all(|tier| tier.discount <= BASIS_POINTS));
+            assert!(tiers.windows(2).all(|pair| pair[0].min_staked < pair[1].min_staked), "fee tiers must be ordered");
+            self.bet_fee = FeeSchedule {
+                min_fee,
+                rate,
+                tiers,
+            };
+            self.bet_fee.clone()
+        }
+
+        /// Sets the betting duration of newly posted news in milliseconds.
+        #[ink(message)]
+        pub fn set_betting_time(
+            &mut self,
+            betting_time_ms: u64,
         ) -> u64 {
             assert_eq!(self.owner, Self::env().caller());
-            self.betting_time = betting_time;
-            return betting_time;
+            self.betting_time = betting_time_ms;
+            betting_time_ms
         }
 
+        /// Sets the voting duration of newly posted news in milliseconds.
         #[ink(message)]
         pub fn set_voting_time(
             &mut self,
-            voting_time: u64,
+            voting_time_ms: u64,
         ) -> u64 {
             assert_eq!(self.owner, Self::env().caller());
-            self.voting_time = voting_time;
-            return self.voting_time;
+            self.voting_time = voting_time_ms;
+            self.voting_time
         }
     }
 
@@ -449,20 +1521,1387 @@ mod platorm {
         bets_yes_promised: u128,
         bets_no_promised: u128,
     ) -> u128 {
-        let bet_weight = amount / pool;
-        let adjusted_weight = percent_of_value(bet_weight, 95);
-        let mut _offered_premium = 0;
-        if choice {
-            _offered_premium = ((pool - bets_yes_promised) * adjusted_weight) + amount;
+        // the bet's weight is its share of the pool, applied before dividing
+        // so that bets smaller than the pool are not rounded away
+        let remaining = if choice {
+            pool.saturating_sub(bets_yes_promised)
         } else {
-            _offered_premium = ((pool - bets_no_promised) * adjusted_weight) + amount;
+            pool.saturating_sub(bets_no_promised)
+        };
+        percent_of_value(remaining * amount / pool, 95) + amount
+    }
+
+    // This function returns what the pool pays for a position: the stake that
+    // `calculate_premium` would turn into the same premium, with the
+    // position's own promise taken off its side first
+    fn calculate_exit_value(
+        amount_promised: u128,
+        choice: bool,
+        pool: u128,
+        bets_yes_promised: u128,
+        bets_no_promised: u128,
+    ) -> u128 {
+        let others_promised = if choice {
+            bets_yes_promised - amount_promised
+        } else {
+            bets_no_promised - amount_promised
+        };
+        let remaining = pool.saturating_sub(others_promised);
+        amount_promised * pool * 100 / (pool * 100 + remaining * 95)
+    }
+
+    // This function returns the probability in basis points that the price of
+    // a marginal bet implies, the inverse of its premium per unit staked
+    fn implied_probability(
+        choice: bool,
+        pool: u128,
+        bets_yes_promised: u128,
+        bets_no_promised: u128,
+    ) -> u128 {
+        let remaining = if choice {
+            pool.saturating_sub(bets_yes_promised)
+        } else {
+            pool.saturating_sub(bets_no_promised)
+        };
+        pool * 100 * PROBABILITY_PRECISION / (pool * 100 + remaining * 95)
+    }
+
+    // This function returns what a news pool may still have to pay its
+    // bettors: the worst outcome until it is settled, then the actual one
+    fn owed_to_bettors(news: &News) -> u128 {
+        let refunds = news.stakes_yes + news.stakes_no;
+        if !news.settled {
+            return news.bets_yes_promised.max(news.bets_no_promised).max(refunds);
+        }
+        let uncertain = news.votes_uncertain > news.votes_yes && news.votes_uncertain > news.votes_no;
+        if uncertain || news.votes_yes == news.votes_no {
+            refunds
+        } else if news.votes_yes > news.votes_no {
+            news.bets_yes_promised
+        } else {
+            news.bets_no_promised
+        }
+    }
+
+    // This function rejects metadata with fields too long to store, or
+    // without a CID or language
+    fn check_metadata(metadata: &Metadata) {
+        assert!(
+            !metadata.cid.is_empty() && metadata.cid.len() <= MAX_CID_LEN,
+            "invalid cid length"
+        );
+        assert!(
+            !metadata.content_type.is_empty() && metadata.content_type.len() <= MAX_CONTENT_TYPE_LEN,
+            "invalid content type length"
+        );
+        assert!(
+            metadata.url.as_ref().is_none_or(|url| url.len() <= MAX_URL_LEN),
+            "url too long"
+        );
+        assert!(
+            !metadata.language.is_empty() && metadata.language.len() <= MAX_LANGUAGE_LEN,
+            "invalid language length"
+        );
+    }
+
+    // This function adds to the total kept for a collateral
+    fn add_to<K: StorageKey>(totals: &mut Mapping<Collateral, u128, K>, collateral: Collateral, amount: u128) {
+        let total = totals.get(collateral).unwrap_or_default();
+        totals.insert(collateral, &(total + amount));
+    }
+
+    // This function takes from the total kept for a collateral
+    fn take_from<K: StorageKey>(totals: &mut Mapping<Collateral, u128, K>, collateral: Collateral, amount: u128) {
+        let total = totals.get(collateral).unwrap_or_default();
+        totals.insert(collateral, &(total - amount));
+    }
+
+    // This function replaces a part of the total kept for a collateral
+    fn shift<K: StorageKey>(totals: &mut Mapping<Collateral, u128, K>, collateral: Collateral, from: u128, to: u128) {
+        let total = totals.get(collateral).unwrap_or_default();
+        totals.insert(collateral, &(total - from + to));
+    }
+
+    // This function returns the fee on a bet of `value` by a bettor with
+    // `staked` CgToken staked
+    fn calculate_fee(value: u128, staked: u128, schedule: &FeeSchedule) -> u128 {
+        let fee = (value * schedule.rate as u128 / BASIS_POINTS as u128).max(schedule.min_fee);
+        let discount = schedule
+            .tiers
+            .iter()
+            .rev()
+            .find(|tier| staked >= tier.min_staked)
+            .map_or(0, |tier| tier.discount);
+        fee - fee * discount as u128 / BASIS_POINTS as u128
+    }
+
+    // This function shares a fee by basis points, leaving the rounding to the
+    // treasury
+    fn split_fee(fee: u128, split: &FeeSplit) -> (u128, u128, u128, u128) {
+        let share = |basis_points: u16| fee * basis_points as u128 / BASIS_POINTS as u128;
+        let author = share(split.author);
+        let voters = share(split.voters);
+        let stakers = share(split.stakers);
+        (fee - author - voters - stakers, author, voters, stakers)
+    }
+
+    // This function returns whether a cast backed the outcome of a news, on a
+    // tie between yes and no every cast did
+    fn backed_outcome(news: &News, cast: Cast) -> bool {
+        if news.votes_uncertain > news.votes_yes && news.votes_uncertain > news.votes_no {
+            cast == Cast::Uncertain
+        } else if news.votes_yes > news.votes_no {
+            cast == Cast::Yes
+        } else if news.votes_yes < news.votes_no {
+            cast == Cast::No
+        } else {
+            true
+        }
+    }
+
+    // This function returns the votes that share the voter fees of a news
+    fn backing_votes(news: &News) -> u128 {
+        [
+            (Cast::Yes, news.votes_yes),
+            (Cast::No, news.votes_no),
+            (Cast::Uncertain, news.votes_uncertain),
+        ]
+        .iter()
+        .filter(|(cast, _)| backed_outcome(news, *cast))
+        .map(|(_, votes)| votes)
+        .sum()
+    }
+
+    // This function returns the weight of a direct vote and what it takes
+    // back from the vote of the voter's delegate. `overridden` is the stake
+    // delegators already took back from the voter by voting themselves and
+    // `own_stake` the stake the voter delegated away, if any. A delegate
+    // never loses more than its vote weighs, should the token's snapshots
+    // disagree.
+    fn override_delegation(
+        voting_power: u128,
+        overridden: u128,
+        own_stake: u128,
+        delegate_vote: Option<u128>,
+    ) -> (u128, u128) {
+        let weight = voting_power.saturating_sub(overridden) + own_stake;
+        let taken_back = delegate_vote.map_or(0, |vote| own_stake.min(vote));
+        (weight, taken_back)
+    }
+
+    // This function returns the tally a cast is counted in
+    fn votes_for(news: &mut News, cast: Cast) -> &mut u128 {
+        match cast {
+            Cast::Yes => &mut news.votes_yes,
+            Cast::No => &mut news.votes_no,
+            Cast::Uncertain => &mut news.votes_uncertain,
         }
-        return _offered_premium;
     }
 
     // This function calculates a percentage of a value
     fn percent_of_value(original_value: u128, reduction_percentage: u128) -> u128 {
-        let reduced_value = original_value * reduction_percentage / 100 + 1;
-        return reduced_value;
+        original_value * reduction_percentage / 100 + 1
+    }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use cgtoken::time::{days, DAY_MS};
+        use ink::env::{call::FromAccountId, test, DefaultEnvironment};
+
+        const POST_FEE: u128 = 10;
+        const BET_FEE: u128 = 1;
+        const BETTING_TIME: u64 = 1_000;
+        const VOTING_TIME: u64 = 1_000;
+        const INITIAL_POOL: u128 = 100;
+
+        fn accounts() -> test::DefaultAccounts<DefaultEnvironment> {
+            test::default_accounts::<DefaultEnvironment>()
+        }
+
+        // cross-contract calls are not available off-chain, so the token is
+        // only a placeholder address
+        fn new_guard() -> CredebilityGuard {
+            CredebilityGuard::with_token(
+                1,
+                POST_FEE,
+                BET_FEE,
+                BETTING_TIME,
+                VOTING_TIME,
+                INITIAL_POOL,
+                CgTokenRef::from_account_id(accounts().django),
+            )
+        }
+
+        // a CIDv1 of raw content with a sha2-256 digest, which stands in for
+        // the digest of the `article`th article
+        fn cid(article: u128) -> Vec<u8> {
+            let mut cid = vec![0x01, 0x55, 0x12, 0x20];
+            cid.extend([0; 16]);
+            cid.extend(article.to_le_bytes());
+            cid
+        }
+
+        fn metadata() -> Metadata {
+            Metadata {
+                cid: cid(0),
+                content_type: String::from("text/html"),
+                url: None,
+                language: String::from("en"),
+            }
+        }
+
+        // posts a news about an article of its own
+        fn post_news(guard: &mut CredebilityGuard) -> u128 {
+            let article = guard.get_counter() + 1;
+            post_article(guard, Metadata { cid: cid(article), ..metadata() })
+        }
+
+        fn post_article(guard: &mut CredebilityGuard, metadata: Metadata) -> u128 {
+            test::set_value_transferred::<DefaultEnvironment>(POST_FEE + INITIAL_POOL);
+            let id = guard.post(metadata, Collateral::Native);
+            test::set_value_transferred::<DefaultEnvironment>(0);
+            id
+        }
+
+        fn place_bet(guard: &mut CredebilityGuard, id: u128, bettor: AccountId, direction: bool, amount: u128) {
+            test::set_caller::<DefaultEnvironment>(bettor);
+            test::set_value_transferred::<DefaultEnvironment>(amount + BET_FEE);
+            guard.bet(direction, id, 0, amount + BET_FEE).unwrap();
+            test::set_value_transferred::<DefaultEnvironment>(0);
+        }
+
+        // records a vote the way `vote` does once voting power is known
+        fn record_vote(
+            guard: &mut CredebilityGuard,
+            id: u128,
+            voter: AccountId,
+            cast: Cast,
+            amount_staked: u128,
+        ) {
+            let mut news = guard.news.get(id).unwrap();
+            *votes_for(&mut news, cast) += amount_staked;
+            guard.news.insert(id, &news);
+            guard.voters.insert((id, voter), &Vote { amount_staked, cast, rewarded: false });
+        }
+
+        #[test]
+        fn delegator_voting_after_delegate_takes_stake_back() {
+            // the delegate holds 50 of its own and 100 delegated
+            let (delegate_weight, _) = override_delegation(150, 0, 0, None);
+            assert_eq!(delegate_weight, 150);
+            let (weight, taken_back) = override_delegation(0, 0, 100, Some(delegate_weight));
+            assert_eq!((weight, taken_back), (100, 100));
+            assert_eq!(delegate_weight - taken_back, 50);
+        }
+
+        #[test]
+        fn delegate_voting_after_delegator_loses_overridden_stake() {
+            let (weight, taken_back) = override_delegation(0, 0, 100, None);
+            assert_eq!((weight, taken_back), (100, 0));
+            // the delegator's stake is now recorded as overridden
+            let (delegate_weight, _) = override_delegation(150, 100, 0, None);
+            assert_eq!(delegate_weight, 50);
+        }
+
+        #[test]
+        fn override_never_takes_more_than_the_delegate_vote() {
+            assert_eq!(override_delegation(0, 0, 100, Some(40)), (100, 40));
+            assert_eq!(override_delegation(50, 80, 0, None), (0, 0));
+        }
+
+        #[ink::test]
+        fn change_vote_moves_weight() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            record_vote(&mut guard, id, accounts().bob, Cast::Yes, 30);
+            record_vote(&mut guard, id, accounts().charlie, Cast::No, 20);
+
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + 1);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(guard.change_vote(Cast::Uncertain, id), 30);
+
+            let news = guard.news.get(id).unwrap();
+            assert_eq!(news.votes_yes, 0);
+            assert_eq!(news.votes_no, 20);
+            assert_eq!(news.votes_uncertain, 30);
+            assert_eq!(guard.voters.get((id, accounts().bob)).unwrap().cast, Cast::Uncertain);
+            assert_eq!(test::recorded_events().count(), 1);
+
+            assert_eq!(guard.change_vote(Cast::No, id), 30);
+            let news = guard.news.get(id).unwrap();
+            assert_eq!(news.votes_no, 50);
+            assert_eq!(news.votes_uncertain, 0);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "account has not voted")]
+        fn change_vote_requires_vote() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + 1);
+            guard.change_vote(Cast::Yes, id);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn change_vote_after_deadline_fails() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            record_vote(&mut guard, id, accounts().alice, Cast::Yes, 30);
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + VOTING_TIME);
+            guard.change_vote(Cast::No, id);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "invalid cid length")]
+        fn post_without_cid_fails() {
+            let mut guard = new_guard();
+            guard.post(Metadata { cid: Vec::new(), ..metadata() }, Collateral::Native);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "url too long")]
+        fn post_with_long_url_fails() {
+            let mut guard = new_guard();
+            let url = "a".repeat(MAX_URL_LEN + 1);
+            guard.post(Metadata { url: Some(url), ..metadata() }, Collateral::Native);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "news already posted")]
+        fn post_of_open_duplicate_fails() {
+            let mut guard = new_guard();
+            post_article(&mut guard, metadata());
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + VOTING_TIME);
+            post_article(&mut guard, Metadata { url: Some(String::from("https://example.com")), ..metadata() });
+        }
+
+        #[ink::test]
+        fn articles_are_found_by_metadata() {
+            let mut guard = new_guard();
+            let first = post_article(&mut guard, metadata());
+            let mut hash = [0; 32];
+            ink::env::hash_bytes::<Blake2x256>(&metadata().cid, &mut hash);
+            let hash = Hash::from(hash);
+            assert_eq!(guard.find_by_metadata(hash), Some(first));
+
+            // other articles can be posted alongside
+            let other = post_news(&mut guard);
+            assert_eq!(guard.find_by_metadata(hash), Some(first));
+            assert_ne!(other, first);
+
+            // the article gets a new market once voting on the last ended
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + VOTING_TIME + 1);
+            let second = post_article(&mut guard, metadata());
+            assert_eq!(guard.find_by_metadata(hash), Some(second));
+        }
+
+        #[ink::test]
+        fn collateral_tokens_are_whitelisted() {
+            let mut guard = new_guard();
+            assert!(guard.is_collateral_token(accounts().django));
+            assert!(!guard.is_collateral_token(accounts().frank));
+            assert!(guard.set_collateral_token(accounts().frank, true));
+            assert!(guard.is_collateral_token(accounts().frank));
+            assert!(!guard.set_collateral_token(accounts().django, false));
+            assert!(!guard.is_collateral_token(accounts().django));
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "collateral not accepted")]
+        fn post_in_unlisted_token_fails() {
+            let mut guard = new_guard();
+            guard.post(metadata(), Collateral::Token(accounts().frank));
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn set_collateral_token_requires_owner() {
+            let mut guard = new_guard();
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            guard.set_collateral_token(accounts().frank, true);
+        }
+
+        #[ink::test]
+        fn fees_are_shared_by_the_split() {
+            let mut guard = new_guard();
+            assert_eq!(guard.get_fee_split().treasury, 10_000);
+            guard.set_fee_split(4_000, 3_000, 2_000, 1_000);
+            assert_eq!(test::recorded_events().count(), 1);
+            let id = post_news(&mut guard);
+            place_bet(&mut guard, id, accounts().bob, true, 10);
+
+            let native = Collateral::Native;
+            // the staker share of fees outside CgToken goes to the treasury
+            assert_eq!(guard.get_fees_containing(native), 4 + 1 + BET_FEE);
+            assert_eq!(guard.get_author_fees(accounts().alice, native), 3);
+            assert_eq!(guard.news.get(id).unwrap().voter_fees, 2);
+            assert_eq!(guard.get_staker_fees(native), 0);
+            assert_eq!(guard.get_reserved_fees(native), 5);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "fee split must add up to 10_000 basis points")]
+        fn fee_split_must_add_up() {
+            let mut guard = new_guard();
+            guard.set_fee_split(4_000, 3_000, 2_000, 999);
+        }
+
+        #[ink::test]
+        fn author_fees_are_paid_out() {
+            let contract = accounts().eve;
+            test::set_callee::<DefaultEnvironment>(contract);
+            let mut guard = new_guard();
+            guard.set_fee_split(4_000, 3_000, 2_000, 1_000);
+            post_news(&mut guard);
+            test::set_account_balance::<DefaultEnvironment>(contract, POST_FEE + INITIAL_POOL);
+
+            assert_eq!(guard.withdraw_author_fees(Collateral::Native), Ok(3));
+            assert_eq!(guard.get_author_fees(accounts().alice, Collateral::Native), 0);
+            assert_eq!(guard.withdraw_author_fees(Collateral::Native), Err(Error::NothingToWithdraw));
+            // there are no staker fees outside CgToken
+            assert_eq!(guard.distribute_staker_fees(Collateral::Native), Err(Error::NothingToWithdraw));
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(guard.distribute_staker_fees(Collateral::Native), Err(Error::NotOwner));
+            test::set_caller::<DefaultEnvironment>(accounts().alice);
+            assert_eq!(guard.withdraw_fees(Collateral::Native, accounts().bob, 5), Ok(()));
+            assert_eq!(guard.get_reserved_fees(Collateral::Native), 2);
+            assert_eq!(test::get_account_balance::<DefaultEnvironment>(contract), Ok(INITIAL_POOL + 2));
+            assert!(guard.check_invariant(Collateral::Native));
+            assert_eq!(test::recorded_events().count(), 3);
+        }
+
+        #[ink::test]
+        fn voter_fees_go_to_votes_backing_the_outcome() {
+            let mut guard = new_guard();
+            guard.set_fee_split(0, 0, 10_000, 0);
+            let id = post_news(&mut guard);
+            record_vote(&mut guard, id, accounts().bob, Cast::Yes, 30);
+            record_vote(&mut guard, id, accounts().charlie, Cast::Yes, 10);
+            record_vote(&mut guard, id, accounts().django, Cast::No, 20);
+
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + VOTING_TIME + 1);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(guard.claim_voter_reward(id), Ok(7));
+            test::set_caller::<DefaultEnvironment>(accounts().charlie);
+            assert_eq!(guard.claim_voter_reward(id), Ok(2));
+            // the rounding dust goes to the treasury with the last share
+            assert_eq!(guard.get_reserved_fees(Collateral::Native), 0);
+            assert_eq!(guard.get_fees_containing(Collateral::Native), 1);
+        }
+
+        #[ink::test]
+        fn voter_fees_without_backing_weight_cannot_be_claimed() {
+            let mut guard = new_guard();
+            guard.set_fee_split(0, 0, 10_000, 0);
+            let id = post_news(&mut guard);
+            record_vote(&mut guard, id, accounts().bob, Cast::Yes, 0);
+
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + VOTING_TIME + 1);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(guard.claim_voter_reward(id), Err(Error::NothingToClaim));
+        }
+
+        #[ink::test]
+        fn voter_fees_skip_the_minority() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            record_vote(&mut guard, id, accounts().bob, Cast::Yes, 30);
+            record_vote(&mut guard, id, accounts().charlie, Cast::No, 20);
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + VOTING_TIME + 1);
+            test::set_caller::<DefaultEnvironment>(accounts().charlie);
+            assert_eq!(guard.claim_voter_reward(id), Err(Error::NothingToClaim));
+        }
+
+        #[ink::test]
+        fn voter_rewards_are_claimed_once_after_voting() {
+            let mut guard = new_guard();
+            guard.set_fee_split(0, 0, 10_000, 0);
+            let id = post_news(&mut guard);
+            record_vote(&mut guard, id, accounts().bob, Cast::Yes, 30);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(guard.claim_voter_reward(id), Err(Error::VotingNotEnded));
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + VOTING_TIME + 1);
+            assert_eq!(guard.claim_voter_reward(id), Ok(10));
+            assert_eq!(guard.claim_voter_reward(id), Err(Error::AlreadyClaimed));
+            test::set_caller::<DefaultEnvironment>(accounts().charlie);
+            assert_eq!(guard.claim_voter_reward(id), Err(Error::NothingToClaim));
+            assert_eq!(guard.claim_voter_reward(id + 1), Err(Error::UnknownNews));
+        }
+
+        #[ink::test]
+        fn unclaimable_voter_fees_go_to_the_treasury() {
+            let mut guard = new_guard();
+            guard.set_fee_split(5_000, 0, 5_000, 0);
+            let id = post_news(&mut guard);
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + VOTING_TIME + 1);
+            guard.pool_claim(id).unwrap();
+            assert_eq!(guard.get_reserved_fees(Collateral::Native), 0);
+            assert_eq!(guard.get_fees_containing(Collateral::Native), POST_FEE);
+        }
+
+        #[test]
+        fn fee_combines_minimum_rate_and_tiers() {
+            let flat = FeeSchedule {
+                min_fee: 5,
+                rate: 0,
+                tiers: Vec::new(),
+            };
+            assert_eq!(calculate_fee(1_000, 0, &flat), 5);
+
+            let schedule = FeeSchedule {
+                min_fee: 5,
+                rate: 200,
+                tiers: vec![
+                    FeeTier { min_staked: 100, discount: 2_500 },
+                    FeeTier { min_staked: 1_000, discount: 5_000 },
+                ],
+            };
+            // small bets pay the minimum, large ones the rate
+            assert_eq!(calculate_fee(100, 0, &schedule), 5);
+            assert_eq!(calculate_fee(250, 0, &schedule), 5);
+            assert_eq!(calculate_fee(1_000, 0, &schedule), 20);
+            assert_eq!(calculate_fee(1_000, 99, &schedule), 20);
+            assert_eq!(calculate_fee(1_000, 100, &schedule), 15);
+            assert_eq!(calculate_fee(1_000, 5_000, &schedule), 10);
+            assert_eq!(calculate_fee(100, 5_000, &schedule), 3);
+        }
+
+        #[ink::test]
+        fn bet_fee_follows_the_schedule() {
+            let mut guard = new_guard();
+            assert_eq!(guard.set_bet_fee(2, 500, Vec::new()).rate, 500);
+            let id = post_news(&mut guard);
+            assert_eq!(guard.get_bet_fee_for(accounts().bob, 100), 5);
+
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            test::set_value_transferred::<DefaultEnvironment>(100);
+            assert_eq!(guard.bet(true, id, 0, 100), Ok(95));
+            assert_eq!(guard.get_fees_containing(Collateral::Native), POST_FEE + 5);
+            assert_eq!(guard.news.get(id).unwrap().stakes_yes, 95);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "fee tiers must be ordered")]
+        fn fee_tiers_must_be_ordered() {
+            let mut guard = new_guard();
+            guard.set_bet_fee(
+                1,
+                100,
+                vec![
+                    FeeTier { min_staked: 1_000, discount: 1_000 },
+                    FeeTier { min_staked: 100, discount: 500 },
+                ],
+            );
+        }
+
+        #[test]
+        fn fee_split_rounds_for_the_treasury() {
+            let split = FeeSplit {
+                treasury: 5_000,
+                author: 2_000,
+                voters: 2_000,
+                stakers: 1_000,
+            };
+            assert_eq!(split_fee(101, &split), (51, 20, 20, 10));
+            assert_eq!(split_fee(1, &split), (1, 0, 0, 0));
+        }
+
+        #[ink::test]
+        fn author_can_open_several_positions() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            assert!(guard.get_positions(id, accounts().alice).is_empty());
+
+            place_bet(&mut guard, id, accounts().alice, true, 10);
+            place_bet(&mut guard, id, accounts().alice, true, 10);
+            place_bet(&mut guard, id, accounts().alice, false, 20);
+
+            let positions = guard.get_positions(id, accounts().alice);
+            assert_eq!(positions.len(), 3);
+            // the second bet on a side is priced against what the first one was promised
+            assert!(positions[1].amount_promised < positions[0].amount_promised);
+            let news = guard.news.get(id).unwrap();
+            assert_eq!(news.bets_yes_promised, positions[0].amount_promised + positions[1].amount_promised);
+            assert_eq!(news.bets_no_promised, positions[2].amount_promised);
+            assert_eq!(guard.get_fees_containing(Collateral::Native), POST_FEE + 3 * BET_FEE);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "too many positions")]
+        fn positions_are_capped() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            for _ in 0..=MAX_POSITIONS {
+                place_bet(&mut guard, id, accounts().bob, true, 1);
+            }
+        }
+
+        #[ink::test]
+        fn durations_are_in_milliseconds() {
+            let start = 1_700_000_000_000;
+            let mut guard = CredebilityGuard::with_token(
+                1,
+                POST_FEE,
+                BET_FEE,
+                days(1),
+                days(2),
+                INITIAL_POOL,
+                CgTokenRef::from_account_id(accounts().django),
+            );
+            test::set_block_timestamp::<DefaultEnvironment>(start);
+            let id = post_news(&mut guard);
+            let news = guard.news.get(id).unwrap();
+            assert_eq!(news.betting_until, start + 24 * 60 * 60 * 1_000);
+            assert_eq!(news.voting_until, start + 3 * DAY_MS);
+
+            // a bet one millisecond before betting closes still goes through
+            test::set_block_timestamp::<DefaultEnvironment>(start + days(1) - 1);
+            place_bet(&mut guard, id, accounts().bob, true, 10);
+            assert_eq!(guard.get_positions(id, accounts().bob).len(), 1);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn bet_after_betting_closed_fails() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME);
+            place_bet(&mut guard, id, accounts().bob, true, 10);
+        }
+
+        #[ink::test]
+        fn bet_is_accepted_while_betting_is_open() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            place_bet(&mut guard, id, accounts().bob, true, 10);
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME - 1);
+            place_bet(&mut guard, id, accounts().bob, false, 10);
+            assert_eq!(guard.get_positions(id, accounts().bob).len(), 2);
+        }
+
+        #[ink::test]
+        fn winners_are_paid_their_premium_and_losers_nothing() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            place_bet(&mut guard, id, accounts().bob, true, 10);
+            place_bet(&mut guard, id, accounts().charlie, false, 10);
+            record_vote(&mut guard, id, accounts().django, Cast::Yes, 30);
+
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + VOTING_TIME + 1);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(guard.claim(id), Ok(20));
+            test::set_caller::<DefaultEnvironment>(accounts().charlie);
+            assert_eq!(guard.claim(id), Ok(0));
+        }
+
+        #[ink::test]
+        fn claim_pays_all_positions_at_once() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            place_bet(&mut guard, id, accounts().bob, true, 10);
+            place_bet(&mut guard, id, accounts().bob, true, 20);
+            place_bet(&mut guard, id, accounts().bob, false, 5);
+            record_vote(&mut guard, id, accounts().charlie, Cast::Yes, 30);
+
+            let positions = guard.get_positions(id, accounts().bob);
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + VOTING_TIME + 1);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(
+                guard.claim(id).unwrap(),
+                positions[0].amount_promised + positions[1].amount_promised
+            );
+            assert!(guard.get_positions(id, accounts().bob).iter().all(|bet| bet.claimed));
+        }
+
+        #[ink::test]
+        fn failed_payouts_are_kept_for_withdrawal() {
+            // the engine fails transfers out of an account without balance
+            let contract = AccountId::from([0x42; 32]);
+            test::set_callee::<DefaultEnvironment>(contract);
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            place_bet(&mut guard, id, accounts().bob, true, 10);
+            record_vote(&mut guard, id, accounts().charlie, Cast::Yes, 30);
+            let promised = guard.get_positions(id, accounts().bob)[0].amount_promised;
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + VOTING_TIME + 1);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(guard.claim(id), Ok(promised));
+            assert_eq!(guard.get_pending_withdrawal(accounts().bob, Collateral::Native), promised);
+            assert_eq!(guard.get_pending_containing(Collateral::Native), promised);
+            assert_eq!(test::recorded_events().count(), 1);
+
+            let bob_balance = test::get_account_balance::<DefaultEnvironment>(accounts().bob).unwrap();
+            test::set_account_balance::<DefaultEnvironment>(contract, promised);
+            assert_eq!(guard.withdraw(Collateral::Native), Ok(promised));
+            assert_eq!(guard.get_pending_withdrawal(accounts().bob, Collateral::Native), 0);
+            assert_eq!(guard.get_pending_containing(Collateral::Native), 0);
+            assert_eq!(
+                test::get_account_balance::<DefaultEnvironment>(accounts().bob),
+                Ok(bob_balance + promised)
+            );
+        }
+
+        #[ink::test]
+        fn withdraw_without_pending_fails() {
+            let mut guard = new_guard();
+            assert_eq!(guard.withdraw(Collateral::Native), Err(Error::NothingToWithdraw));
+        }
+
+        #[ink::test]
+        fn claim_many_skips_what_cannot_be_claimed() {
+            let mut guard = new_guard();
+            let ended = post_news(&mut guard);
+            place_bet(&mut guard, ended, accounts().bob, true, 10);
+            record_vote(&mut guard, ended, accounts().charlie, Cast::Yes, 30);
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME);
+            let open = post_news(&mut guard);
+            place_bet(&mut guard, open, accounts().bob, true, 10);
+
+            let promised = guard.get_positions(ended, accounts().bob)[0].amount_promised;
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + VOTING_TIME + 1);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(
+                guard.claim_many(vec![ended, open, ended, 99]),
+                vec![Ok(promised), Err(Error::VotingNotEnded), Err(Error::NothingToClaim), Err(Error::UnknownNews)]
+            );
+            assert!(guard.get_positions(ended, accounts().bob)[0].claimed);
+            assert!(!guard.get_positions(open, accounts().bob)[0].claimed);
+        }
+
+        #[ink::test]
+        fn pool_claim_many_skips_what_cannot_be_claimed() {
+            let mut guard = new_guard();
+            let ended = post_news(&mut guard);
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME);
+            let open = post_news(&mut guard);
+
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + VOTING_TIME + 1);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(guard.pool_claim_many(vec![ended]), vec![Err(Error::NotAuthor)]);
+            test::set_caller::<DefaultEnvironment>(accounts().alice);
+            assert_eq!(
+                guard.pool_claim_many(vec![ended, open, ended]),
+                vec![Ok(INITIAL_POOL), Err(Error::VotingNotEnded), Err(Error::AlreadyClaimed)]
+            );
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "too many recipients")]
+        fn distribute_tokens_is_capped() {
+            let mut guard = new_guard();
+            guard.distribute_tokens(vec![(accounts().bob, 1); MAX_RECIPIENTS + 1]);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "too many ids")]
+        fn claim_many_is_capped() {
+            let mut guard = new_guard();
+            guard.claim_many(vec![1; MAX_BATCH_CLAIMS + 1]);
+        }
+
+        #[ink::test]
+        fn claim_refunds_on_uncertain_outcome() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            place_bet(&mut guard, id, accounts().bob, true, 10);
+            place_bet(&mut guard, id, accounts().bob, false, 5);
+            record_vote(&mut guard, id, accounts().charlie, Cast::Uncertain, 30);
+
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + VOTING_TIME + 1);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(guard.claim(id), Ok(15));
+        }
+
+        #[ink::test]
+        fn claim_twice_fails() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            place_bet(&mut guard, id, accounts().bob, true, 10);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(guard.claim(id), Err(Error::VotingNotEnded));
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + VOTING_TIME + 1);
+            guard.claim(id).unwrap();
+            assert_eq!(guard.claim(id), Err(Error::NothingToClaim));
+        }
+
+        #[ink::test]
+        fn pool_claim_twice_fails() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            assert_eq!(guard.pool_claim(id), Err(Error::VotingNotEnded));
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + VOTING_TIME + 1);
+            assert_eq!(guard.pool_claim(id), Ok(INITIAL_POOL));
+            assert_eq!(guard.pool_claim(id), Err(Error::AlreadyClaimed));
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(guard.pool_claim(id), Err(Error::NotAuthor));
+        }
+
+        #[ink::test]
+        fn quote_bet_matches_bet() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            place_bet(&mut guard, id, accounts().charlie, true, 30);
+
+            let (premium, probability) = guard.quote_bet(id, true, 10);
+            assert_eq!(premium, 15);
+            assert_eq!(probability, 6_666);
+            place_bet(&mut guard, id, accounts().bob, true, 10);
+            assert_eq!(guard.get_positions(id, accounts().bob)[0].amount_promised, premium);
+        }
+
+        #[ink::test]
+        fn quote_of_nothing_is_zero() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            assert_eq!(guard.quote_bet(id, true, 0), (0, 0));
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "initial pool must not be empty")]
+        fn empty_initial_pool_fails() {
+            CredebilityGuard::with_token(
+                1,
+                POST_FEE,
+                BET_FEE,
+                BETTING_TIME,
+                VOTING_TIME,
+                0,
+                CgTokenRef::from_account_id(accounts().django),
+            );
+        }
+
+        #[ink::test]
+        fn bet_fails_below_min_premium() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            let (premium, _) = guard.quote_bet(id, false, 10);
+            place_bet(&mut guard, id, accounts().charlie, false, 30);
+
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            test::set_value_transferred::<DefaultEnvironment>(10 + BET_FEE);
+            assert_eq!(guard.bet(false, id, premium, 10 + BET_FEE), Err(Error::PremiumBelowMinimum));
+            assert!(guard.get_positions(id, accounts().bob).is_empty());
+            assert_eq!(guard.get_fees_containing(Collateral::Native), POST_FEE + BET_FEE);
+        }
+
+        #[ink::test]
+        fn market_state_follows_bets() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            let state = guard.get_market_state(id);
+            assert_eq!(state.implied_yes, 5_128);
+            assert_eq!(state.implied_yes, state.implied_no);
+            assert_eq!(state.remaining_yes, INITIAL_POOL);
+
+            place_bet(&mut guard, id, accounts().bob, false, 30);
+            place_bet(&mut guard, id, accounts().bob, false, 10);
+            place_bet(&mut guard, id, accounts().charlie, true, 5);
+            let state = guard.get_market_state(id);
+            let news = guard.news.get(id).unwrap();
+            assert!(state.implied_no > state.implied_yes);
+            assert_eq!(state.remaining_no, news.pool - news.bets_no_promised);
+            assert_eq!(state.staked_no, 40);
+            assert_eq!(state.staked_yes, 5);
+            assert_eq!(state.bettor_count, 2);
+
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            guard.transfer_bet(id, accounts().charlie);
+            assert_eq!(guard.get_market_state(id).bettor_count, 1);
+            test::set_caller::<DefaultEnvironment>(accounts().charlie);
+            guard.sell_position(id, 0).unwrap();
+            let state = guard.get_market_state(id);
+            assert_eq!((state.staked_yes, state.staked_no, state.bettor_count), (0, 0, 0));
+        }
+
+        #[ink::test]
+        fn transfer_bet_moves_positions() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            place_bet(&mut guard, id, accounts().bob, true, 10);
+            place_bet(&mut guard, id, accounts().bob, false, 10);
+            place_bet(&mut guard, id, accounts().charlie, true, 10);
+
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(guard.transfer_bet(id, accounts().charlie), 2);
+            assert!(guard.get_positions(id, accounts().bob).is_empty());
+            let positions = guard.get_positions(id, accounts().charlie);
+            assert_eq!(positions.len(), 3);
+            assert!(!positions[2].direction);
+            assert_eq!(test::recorded_events().count(), 1);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "account has no positions")]
+        fn transfer_bet_requires_positions() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            guard.transfer_bet(id, accounts().charlie);
+        }
+
+        #[ink::test]
+        fn sell_position_releases_liability() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            place_bet(&mut guard, id, accounts().bob, true, 10);
+            place_bet(&mut guard, id, accounts().charlie, true, 40);
+            let promised = guard.get_positions(id, accounts().bob)[0].amount_promised;
+            assert_eq!(promised, 20);
+
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            // charlie's bet used up the yes side, so bob's position gained value
+            assert_eq!(guard.sell_position(id, 0), Ok(13));
+            assert!(guard.get_positions(id, accounts().bob).is_empty());
+            let news = guard.news.get(id).unwrap();
+            assert_eq!(news.bets_yes_promised, guard.get_positions(id, accounts().charlie)[0].amount_promised);
+            assert_eq!(news.pool, INITIAL_POOL + 10 + 40 - 13);
+            assert_eq!(test::recorded_events().count(), 1);
+        }
+
+        #[ink::test]
+        fn sell_position_respects_min_out() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            place_bet(&mut guard, id, accounts().bob, true, 10);
+            assert_eq!(guard.sell_position(id, 11), Err(Error::PayoutBelowMinimum));
+            assert_eq!(guard.get_positions(id, accounts().bob).len(), 1);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn sell_position_after_betting_closed_fails() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            place_bet(&mut guard, id, accounts().bob, true, 10);
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME);
+            guard.sell_position(id, 0).unwrap();
+        }
+
+        #[test]
+        fn exit_value_inverts_premium() {
+            let premium = calculate_premium(50, true, 100, 0, 0);
+            assert_eq!(calculate_exit_value(premium, true, 100, premium, 0), 50);
+            // once others used up the side, the position is worth its premium
+            assert_eq!(calculate_exit_value(20, false, 100, 0, 120), 20);
+        }
+
+        #[ink::test]
+        fn pools_are_settled_against_the_balance() {
+            // off-chain the contract runs as alice unless told otherwise
+            let contract = accounts().eve;
+            test::set_callee::<DefaultEnvironment>(contract);
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            place_bet(&mut guard, id, accounts().bob, true, 10);
+            place_bet(&mut guard, id, accounts().charlie, false, 20);
+            assert_eq!(guard.get_pools_containing(Collateral::Native), INITIAL_POOL + 30);
+            // value transferred off-chain is not credited to the contract
+            let held = POST_FEE + INITIAL_POOL + 30 + 2 * BET_FEE;
+            test::set_account_balance::<DefaultEnvironment>(contract, held - 1);
+            assert!(!guard.check_invariant(Collateral::Native));
+            test::set_account_balance::<DefaultEnvironment>(contract, held);
+            assert!(guard.check_invariant(Collateral::Native));
+
+            record_vote(&mut guard, id, accounts().django, Cast::Yes, 30);
+            test::set_block_timestamp::<DefaultEnvironment>(BETTING_TIME + VOTING_TIME + 1);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            let won = guard.claim(id).unwrap();
+            test::set_caller::<DefaultEnvironment>(accounts().charlie);
+            assert_eq!(guard.claim(id), Ok(0));
+            test::set_caller::<DefaultEnvironment>(accounts().alice);
+            assert_eq!(guard.pool_claim(id), Ok(INITIAL_POOL + 30 - won));
+
+            assert_eq!(guard.get_pools_containing(Collateral::Native), 0);
+            assert_eq!(guard.news.get(id).unwrap().pool, 0);
+            assert_eq!(test::get_account_balance::<DefaultEnvironment>(contract), Ok(POST_FEE + 2 * BET_FEE));
+            assert!(guard.check_invariant(Collateral::Native));
+        }
+
+        #[ink::test]
+        fn bet_beyond_collateral_fails() {
+            let mut guard = new_guard();
+            let id = post_news(&mut guard);
+            // a stake this far above the pool is promised more than pool and stake hold
+            test::set_value_transferred::<DefaultEnvironment>(106 + BET_FEE);
+            assert_eq!(guard.bet(true, id, 0, 106 + BET_FEE), Err(Error::InsufficientCollateral));
+            assert_eq!(guard.get_total_liabilities(Collateral::Native), 0);
+            assert_eq!(guard.get_fees_containing(Collateral::Native), POST_FEE);
+
+            place_bet(&mut guard, id, accounts().bob, true, 105);
+            let news = guard.news.get(id).unwrap();
+            assert_eq!(guard.get_total_liabilities(Collateral::Native), news.bets_yes_promised);
+            assert_eq!(news.bets_yes_promised, news.pool);
+        }
+
+        #[ink::test]
+        fn fee_withdrawal_leaves_pools_covered() {
+            let contract = accounts().eve;
+            test::set_callee::<DefaultEnvironment>(contract);
+            let mut guard = new_guard();
+            post_news(&mut guard);
+            // the balance falls 4 short of what the contract holds
+            test::set_account_balance::<DefaultEnvironment>(contract, POST_FEE + INITIAL_POOL - 4);
+            assert_eq!(
+                guard.withdraw_fees(Collateral::Native, accounts().bob, POST_FEE - 3),
+                Err(Error::InsufficientFees)
+            );
+            assert_eq!(guard.withdraw_fees(Collateral::Native, accounts().bob, POST_FEE - 4), Ok(()));
+            assert_eq!(guard.get_fees_containing(Collateral::Native), 4);
+            assert_eq!(test::get_account_balance::<DefaultEnvironment>(contract), Ok(INITIAL_POOL));
+        }
+
+        #[ink::test]
+        fn fees_can_be_withdrawn_in_parts() {
+            let contract = accounts().eve;
+            test::set_callee::<DefaultEnvironment>(contract);
+            let mut guard = new_guard();
+            post_news(&mut guard);
+            test::set_account_balance::<DefaultEnvironment>(contract, POST_FEE + INITIAL_POOL);
+            let treasury = accounts().frank;
+            let treasury_balance = test::get_account_balance::<DefaultEnvironment>(treasury).unwrap();
+            guard.set_fee_manager(accounts().bob);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(guard.withdraw_fees(Collateral::Native, treasury, 3), Ok(()));
+            assert_eq!(guard.withdraw_fees(Collateral::Native, treasury, POST_FEE - 3), Ok(()));
+            assert_eq!(guard.withdraw_fees(Collateral::Native, treasury, 1), Err(Error::InsufficientFees));
+            assert_eq!(guard.get_fees_containing(Collateral::Native), 0);
+            assert_eq!(
+                test::get_account_balance::<DefaultEnvironment>(treasury),
+                Ok(treasury_balance + POST_FEE)
+            );
+            assert_eq!(test::recorded_events().count(), 3);
+        }
+
+        #[ink::test]
+        fn changing_the_fee_manager_is_recorded() {
+            let mut guard = new_guard();
+            assert_eq!(guard.set_fee_manager(accounts().bob), accounts().bob);
+            assert_eq!(guard.get_fee_manager(), accounts().bob);
+            assert_eq!(test::recorded_events().count(), 1);
+        }
+
+        #[ink::test]
+        fn fee_withdrawal_requires_fee_manager() {
+            let mut guard = new_guard();
+            post_news(&mut guard);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(
+                guard.withdraw_fees(Collateral::Native, accounts().bob, 1),
+                Err(Error::NotFeeManager)
+            );
+            assert_eq!(guard.get_fees_containing(Collateral::Native), POST_FEE);
+        }
+
+        // xorshift, so that failing sequences can be replayed from their seed
+        struct Rng(u64);
+
+        impl Rng {
+            fn below(&mut self, bound: u64) -> u64 {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                self.0 % bound
+            }
+        }
+
+        #[ink::test]
+        fn random_markets_stay_solvent() {
+            let contract = accounts().eve;
+            test::set_callee::<DefaultEnvironment>(contract);
+            let mut guard = new_guard();
+            guard.set_fee_split(4_000, 3_000, 2_000, 1_000);
+            let users = [accounts().alice, accounts().bob, accounts().charlie, accounts().frank];
+            let casts = [Cast::Yes, Cast::No, Cast::Uncertain];
+            let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+            let mut now = 0;
+            let mut balance = 0;
+            let mut ids = Vec::new();
+
+            for _ in 0..5_000 {
+                now += rng.below(20);
+                test::set_block_timestamp::<DefaultEnvironment>(now);
+                let user = users[rng.below(users.len() as u64) as usize];
+                test::set_caller::<DefaultEnvironment>(user);
+                let id = if ids.is_empty() || rng.below(40) == 0 {
+                    balance += POST_FEE + INITIAL_POOL;
+                    test::set_account_balance::<DefaultEnvironment>(contract, balance);
+                    ids.push(post_news(&mut guard));
+                    continue;
+                } else {
+                    ids[rng.below(ids.len() as u64) as usize]
+                };
+                let news = guard.news.get(id).unwrap();
+                let positions = guard.get_positions(id, user);
+                match rng.below(4) {
+                    0 if now < news.betting_until && positions.len() < MAX_POSITIONS => {
+                        let amount = 1 + rng.below(150) as u128;
+                        test::set_value_transferred::<DefaultEnvironment>(amount + BET_FEE);
+                        if guard.bet(rng.below(2) == 0, id, 0, amount + BET_FEE).is_ok() {
+                            balance += amount + BET_FEE;
+                            test::set_account_balance::<DefaultEnvironment>(contract, balance);
+                        }
+                        test::set_value_transferred::<DefaultEnvironment>(0);
+                    }
+                    1 if now < news.betting_until && !positions.is_empty() => {
+                        // a sale the pool cannot cover fails before touching storage
+                        match guard.sell_position(id, 0) {
+                            Ok(payout) => balance -= payout,
+                            Err(error) => assert_eq!(error, Error::InsufficientCollateral),
+                        }
+                    }
+                    2 if news.betting_until <= now
+                        && now < news.voting_until
+                        && guard.voters.get((id, user)).is_none() =>
+                    {
+                        // weights as `vote` would read them
+                        let cast = casts[rng.below(3) as usize];
+                        record_vote(&mut guard, id, user, cast, 1 + rng.below(50) as u128);
+                    }
+                    3 if news.voting_until < now => {
+                        let vote = guard.voters.get((id, user));
+                        if positions.iter().any(|bet| !bet.claimed) {
+                            balance -= guard.claim(id).unwrap();
+                        } else if news.author == user && !news.claimed {
+                            balance -= guard.pool_claim(id).unwrap();
+                        } else if vote.is_some_and(|vote| !vote.rewarded && backed_outcome(&news, vote.cast)) {
+                            balance -= guard.claim_voter_reward(id).unwrap();
+                        } else if guard.get_author_fees(user, Collateral::Native) > 0 {
+                            balance -= guard.withdraw_author_fees(Collateral::Native).unwrap();
+                        }
+                    }
+                    _ => {}
+                }
+                assert_eq!(test::get_account_balance::<DefaultEnvironment>(contract), Ok(balance));
+                assert!(guard.check_invariant(Collateral::Native));
+                let news = guard.news.get(id).unwrap();
+                assert!(owed_to_bettors(&news) <= news.pool);
+            }
+            assert!(guard.get_total_liabilities(Collateral::Native) > 0);
+            // fees can be paid out without touching the pools
+            test::set_caller::<DefaultEnvironment>(accounts().alice);
+            let fees = guard.get_fees_containing(Collateral::Native);
+            assert_eq!(guard.withdraw_fees(Collateral::Native, accounts().alice, fees), Ok(()));
+            balance -= fees;
+            assert_eq!(test::get_account_balance::<DefaultEnvironment>(contract), Ok(balance));
+            assert!(guard.check_invariant(Collateral::Native));
+        }
+
+        #[test]
+        fn premium_scales_with_bet_size() {
+            // 95% of the bet's share of the open pool, plus the stake itself
+            assert_eq!(calculate_premium(10, true, 100, 0, 0), 20);
+            assert_eq!(calculate_premium(50, false, 100, 0, 0), 98);
+            assert_eq!(calculate_premium(10, true, 100, 60, 0), 14);
+            // what one side was promised does not price the other
+            assert_eq!(calculate_premium(10, false, 100, 60, 0), 20);
+            // a fully promised side only returns the stake and rounding
+            assert_eq!(calculate_premium(10, true, 100, 150, 0), 11);
+        }
+
+        #[test]
+        fn cast_rejects_unknown_values() {
+            use scale::{Decode, Encode};
+
+            assert_eq!(Cast::Uncertain.encode(), [2]);
+            assert_eq!(Cast::decode(&mut &[1u8][..]), Ok(Cast::No));
+            assert!(Cast::decode(&mut &[3u8][..]).is_err());
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use cgtoken::time::seconds;
+        use ink_e2e::build_message;
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        const POST_FEE: u128 = 10;
+        const BET_FEE: u128 = 1;
+        const BETTING_TIME: u64 = seconds(6);
+        const INITIAL_POOL: u128 = 100;
+
+        // A platform owned by alice, which holds the CgToken supply, and the
+        // address of that CgToken.
+        macro_rules! setup {
+            ($client:ident, $voting_time:expr) => {{
+                let cgtoken_code = $client
+                    .upload("cgtoken", &ink_e2e::alice(), None)
+                    .await
+                    .expect("cgtoken upload failed")
+                    .code_hash;
+                let constructor = CredebilityGuardRef::new(1, POST_FEE, BET_FEE, BETTING_TIME, $voting_time, INITIAL_POOL, cgtoken_code);
+                let platform = $client
+                    .instantiate("platform", &ink_e2e::alice(), constructor, 0, None)
+                    .await
+                    .expect("platform instantiate failed")
+                    .account_id;
+                let token = build_message::<CredebilityGuardRef>(platform.clone())
+                    .call(|platform| platform.get_token());
+                let cgtoken = $client
+                    .call_dry_run(&ink_e2e::alice(), &token, 0, None)
+                    .await
+                    .return_value()
+                    .to_account_id();
+                (platform, cgtoken)
+            }};
+        }
+
+        fn metadata(article: u8) -> Metadata {
+            let mut cid = vec![0x01, 0x55, 0x12, 0x20];
+            cid.extend_from_slice(&[article; 32]);
+            Metadata {
+                cid,
+                content_type: String::from("text/plain"),
+                url: None,
+                language: String::from("en"),
+            }
+        }
+
+        #[ink_e2e::test(additional_contracts = "cgtoken/Cargo.toml")]
+        async fn news_can_be_posted_bet_and_claimed_in_cgtoken(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (platform, cgtoken) = setup!(client, 0);
+            let collateral = Collateral::Token(cgtoken.clone());
+            let alice = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+
+            let distribute = build_message::<CredebilityGuardRef>(platform.clone())
+                .call(|platform| platform.distribute_tokens(vec![(bob.clone(), 1_000)]));
+            client
+                .call(&ink_e2e::alice(), distribute, 0, None)
+                .await
+                .expect("distribute_tokens failed");
+            let approve = build_message::<CgTokenRef>(cgtoken.clone())
+                .call(|cgtoken| cgtoken.approve(platform.clone(), 1_000));
+            client
+                .call(&ink_e2e::bob(), approve, 0, None)
+                .await
+                .expect("approve failed");
+
+            // post and bet draw the collateral from bob's allowance
+            let post = build_message::<CredebilityGuardRef>(platform.clone())
+                .call(|platform| platform.post(metadata(7), collateral));
+            let id = client
+                .call(&ink_e2e::bob(), post, 0, None)
+                .await
+                .expect("post failed")
+                .return_value();
+            let bet = build_message::<CredebilityGuardRef>(platform.clone())
+                .call(|platform| platform.bet(true, id, 0, 10 + BET_FEE));
+            client
+                .call(&ink_e2e::bob(), bet, 0, None)
+                .await
+                .expect("bet failed");
+
+            // what backs the news cannot be handed out by the owner
+            let balance = build_message::<CgTokenRef>(cgtoken.clone())
+                .call(|cgtoken| cgtoken.balance_of(platform.clone()));
+            let balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance, 0, None)
+                .await
+                .return_value();
+            let drain = build_message::<CredebilityGuardRef>(platform.clone())
+                .call(|platform| platform.distribute_tokens(vec![(alice.clone(), balance)]));
+            assert!(client.call(&ink_e2e::alice(), drain, 0, None).await.is_err());
+
+            std::thread::sleep(std::time::Duration::from_millis(BETTING_TIME));
+            // without votes the outcome is a tie, which refunds the stake
+            let claim = build_message::<CredebilityGuardRef>(platform.clone())
+                .call(|platform| platform.claim(id));
+            let claimed = client
+                .call(&ink_e2e::bob(), claim, 0, None)
+                .await
+                .expect("claim failed")
+                .return_value();
+            assert_eq!(claimed, Ok(10));
+            let pool_claim = build_message::<CredebilityGuardRef>(platform.clone())
+                .call(|platform| platform.pool_claim(id));
+            let claimed = client
+                .call(&ink_e2e::bob(), pool_claim, 0, None)
+                .await
+                .expect("pool_claim failed")
+                .return_value();
+            assert_eq!(claimed, Ok(INITIAL_POOL));
+
+            let balance = build_message::<CgTokenRef>(cgtoken.clone())
+                .call(|cgtoken| cgtoken.balance_of(bob.clone()));
+            let balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance, 0, None)
+                .await
+                .return_value();
+            assert_eq!(balance, 1_000 - POST_FEE - BET_FEE);
+            let held = build_message::<CredebilityGuardRef>(platform.clone())
+                .call(|platform| platform.check_invariant(collateral));
+            assert!(client.call_dry_run(&ink_e2e::alice(), &held, 0, None).await.return_value());
+            Ok(())
+        }
+
+        #[ink_e2e::test(additional_contracts = "cgtoken/Cargo.toml")]
+        async fn direct_votes_override_delegation_in_either_order(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (platform, cgtoken) = setup!(client, seconds(60));
+            let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let charlie = ink_e2e::account_id(ink_e2e::AccountKeyring::Charlie);
+            let distribute = build_message::<CredebilityGuardRef>(platform.clone())
+                .call(|platform| platform.distribute_tokens(vec![(bob.clone(), 100), (charlie.clone(), 50)]));
+            client
+                .call(&ink_e2e::alice(), distribute, 0, None)
+                .await
+                .expect("distribute_tokens failed");
+
+            // bob votes with 100 of his own and 50 delegated by charlie
+            let stake = build_message::<CgTokenRef>(cgtoken.clone()).call(|cgtoken| cgtoken.stake(100));
+            client.call(&ink_e2e::bob(), stake, 0, None).await.expect("stake failed");
+            let stake = build_message::<CgTokenRef>(cgtoken.clone()).call(|cgtoken| cgtoken.stake(50));
+            client.call(&ink_e2e::charlie(), stake, 0, None).await.expect("stake failed");
+            let delegate = build_message::<CgTokenRef>(cgtoken.clone())
+                .call(|cgtoken| cgtoken.delegate(bob.clone()));
+            client.call(&ink_e2e::charlie(), delegate, 0, None).await.expect("delegate failed");
+
+            let mut ids = Vec::new();
+            for article in [1, 2] {
+                let post = build_message::<CredebilityGuardRef>(platform.clone())
+                    .call(|platform| platform.post(metadata(article), Collateral::Native));
+                let id = client
+                    .call(&ink_e2e::alice(), post, POST_FEE + INITIAL_POOL, None)
+                    .await
+                    .expect("post failed")
+                    .return_value();
+                ids.push(id);
+            }
+            std::thread::sleep(std::time::Duration::from_millis(BETTING_TIME));
+
+            // the delegate votes first, then the delegator takes its stake back
+            let vote = build_message::<CredebilityGuardRef>(platform.clone())
+                .call(|platform| platform.vote(Cast::Yes, ids[0]));
+            let weight = client.call(&ink_e2e::bob(), vote, 0, None).await.expect("vote failed").return_value();
+            assert_eq!(weight, 150);
+            let vote = build_message::<CredebilityGuardRef>(platform.clone())
+                .call(|platform| platform.vote(Cast::No, ids[0]));
+            let weight = client.call(&ink_e2e::charlie(), vote, 0, None).await.expect("vote failed").return_value();
+            assert_eq!(weight, 50);
+
+            // the delegator votes first, the delegate is left its own stake
+            let vote = build_message::<CredebilityGuardRef>(platform.clone())
+                .call(|platform| platform.vote(Cast::No, ids[1]));
+            let weight = client.call(&ink_e2e::charlie(), vote, 0, None).await.expect("vote failed").return_value();
+            assert_eq!(weight, 50);
+            let vote = build_message::<CredebilityGuardRef>(platform.clone())
+                .call(|platform| platform.vote(Cast::Yes, ids[1]));
+            let weight = client.call(&ink_e2e::bob(), vote, 0, None).await.expect("vote failed").return_value();
+            assert_eq!(weight, 100);
+
+            for id in ids {
+                let news = build_message::<CredebilityGuardRef>(platform.clone())
+                    .call(|platform| platform.get_news(id));
+                let news = client
+                    .call_dry_run(&ink_e2e::alice(), &news, 0, None)
+                    .await
+                    .return_value()
+                    .expect("news exists");
+                assert_eq!((news.votes_yes, news.votes_no), (100, 50));
+            }
+            Ok(())
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,100 @@
+//! Computes the content identifiers news are posted with, so that posters
+//! and indexers derive the same `Metadata` from an article.
+//!
+//! Articles are addressed like IPFS addresses a file added with
+//! `--raw-leaves --cid-version 1` that fits into one block: a CIDv1 of the
+//! raw codec naming the sha2-256 digest of the article's bytes.
+
+use blake2::{digest::consts::U32, Blake2b};
+use platform::Metadata;
+use sha2::{Digest, Sha256};
+
+pub const CID_V1: u8 = 0x01;
+/// Multicodec of content addressed as plain bytes.
+pub const RAW: u8 = 0x55;
+/// Multihash code of sha2-256.
+pub const SHA2_256: u8 = 0x12;
+const SHA2_256_LEN: u8 = 32;
+
+// RFC 4648 base32 in lower case, as multibase prefix `b` uses it
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// Returns the binary CIDv1 of `payload`. All codes are below `0x80`, so
+/// each of their varints takes a single byte.
+pub fn cid(payload: &[u8]) -> Vec<u8> {
+    let mut cid = vec![CID_V1, RAW, SHA2_256, SHA2_256_LEN];
+    cid.extend_from_slice(&Sha256::digest(payload));
+    cid
+}
+
+/// Encodes a binary CID the way IPFS prints it, as multibase base32
+/// starting with `b`.
+pub fn to_string(cid: &[u8]) -> String {
+    let mut encoded = String::with_capacity(1 + (cid.len() * 8).div_ceil(5));
+    encoded.push('b');
+    let mut buffer: u16 = 0;
+    let mut bits = 0;
+    for byte in cid {
+        buffer = (buffer << 8) | u16::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[usize::from((buffer >> bits) & 0x1f)] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[usize::from((buffer << (5 - bits)) & 0x1f)] as char);
+    }
+    encoded
+}
+
+/// Returns the hash the platform indexes news of an article by, to look
+/// them up with `find_by_metadata`.
+pub fn metadata_hash(cid: &[u8]) -> [u8; 32] {
+    Blake2b::<U32>::digest(cid).into()
+}
+
+/// Builds the metadata to post an article with.
+pub fn metadata(payload: &[u8], content_type: &str, url: Option<&str>, language: &str) -> Metadata {
+    Metadata {
+        cid: cid(payload),
+        content_type: content_type.into(),
+        url: url.map(Into::into),
+        language: language.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cid_names_the_sha2_256_digest() {
+        let cid = cid(b"hello world");
+        assert_eq!(cid[..4], [CID_V1, RAW, SHA2_256, SHA2_256_LEN]);
+        assert_eq!(cid[4..], Sha256::digest(b"hello world")[..]);
+    }
+
+    #[test]
+    fn cid_prints_like_ipfs() {
+        assert_eq!(
+            to_string(&cid(b"hello world")),
+            "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e"
+        );
+    }
+
+    #[test]
+    fn metadata_hash_matches_the_platform() {
+        let cid = cid(b"hello world");
+        let mut hash = [0; 32];
+        ink::env::hash_bytes::<ink::env::hash::Blake2x256>(&cid, &mut hash);
+        assert_eq!(metadata_hash(&cid), hash);
+    }
+
+    #[test]
+    fn metadata_carries_the_cid() {
+        let metadata = metadata(b"hello world", "text/plain", None, "en");
+        assert_eq!(metadata.cid, cid(b"hello world"));
+        assert_eq!(metadata.language, "en");
+    }
+}
@@ -0,0 +1,303 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+//! A PSP22 token that tries to claim a news pool a second time while the
+//! platform pays the first claim out in it. Only used by the e2e tests, it
+//! mints whatever is drawn from an allowance and should never be listed as
+//! collateral outside of them.
+
+pub use self::reentrant_token::ReentrantTokenRef;
+
+#[ink::contract]
+mod reentrant_token {
+    use cgtoken::psp22::{PSP22Error, PSP22};
+    use ink::{
+        codegen::TraitCallBuilder,
+        env::{call::FromAccountId, CallFlags},
+        prelude::{string::String, vec::Vec},
+        storage::Mapping,
+    };
+    use platform::{Collateral, CredebilityGuardRef, Error, Metadata};
+
+    #[ink(storage)]
+    #[derive(Default)]
+    pub struct ReentrantToken {
+        balances: Mapping<AccountId, Balance>,
+        // the news claimed again from within `transfer`
+        attack: Option<u128>,
+        // whether the last claim from within `transfer` went through
+        reentered: Option<bool>,
+        // makes `transfer` fail instead
+        refuse: bool,
+    }
+
+    impl ReentrantToken {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Posts a news in this token on `platform`, with the token as author.
+        #[ink(message)]
+        pub fn post(&mut self, platform: AccountId, metadata: Metadata) -> u128 {
+            let collateral = Collateral::Token(self.env().account_id());
+            Self::platform(platform)
+                .call_mut()
+                .post(metadata, collateral)
+                // the platform draws the collateral from this token
+                .call_flags(CallFlags::default().set_allow_reentry(true))
+                .invoke()
+        }
+
+        /// Claims the pool of a news the token posted.
+        #[ink(message)]
+        pub fn pool_claim(&mut self, platform: AccountId, id: u128) -> Result<u128, Error> {
+            Self::platform(platform)
+                .call_mut()
+                .pool_claim(id)
+                // the platform pays the pool out in this token
+                .call_flags(CallFlags::default().set_allow_reentry(true))
+                .invoke()
+        }
+
+        /// Withdraws what the platform could not pay the token in itself.
+        #[ink(message)]
+        pub fn withdraw(&mut self, platform: AccountId) -> Result<u128, Error> {
+            let collateral = Collateral::Token(self.env().account_id());
+            Self::platform(platform)
+                .call_mut()
+                .withdraw(collateral)
+                .call_flags(CallFlags::default().set_allow_reentry(true))
+                .invoke()
+        }
+
+        /// Makes the next payout in this token claim the pool of `id` again.
+        #[ink(message)]
+        pub fn set_attack(&mut self, id: Option<u128>) {
+            self.attack = id;
+        }
+
+        #[ink(message)]
+        pub fn set_refuse(&mut self, refuse: bool) {
+            self.refuse = refuse;
+        }
+
+        #[ink(message)]
+        pub fn get_reentered(&self) -> Option<bool> {
+            self.reentered
+        }
+
+        fn platform(platform: AccountId) -> CredebilityGuardRef {
+            FromAccountId::from_account_id(platform)
+        }
+    }
+
+    impl PSP22 for ReentrantToken {
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> Balance {
+            self.balances.get(owner).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        fn transfer(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            if self.refuse {
+                return Err(PSP22Error::Custom(String::from("refused")));
+            }
+            let from = self.env().caller();
+            let balance = self.balance_of(from);
+            if balance < value {
+                return Err(PSP22Error::InsufficientBalance);
+            }
+            self.balances.insert(from, &(balance - value));
+            self.balances.insert(to, &(self.balance_of(to) + value));
+            if let Some(id) = self.attack.take() {
+                let claimed = Self::platform(from)
+                    .call_mut()
+                    .pool_claim(id)
+                    .call_flags(CallFlags::default().set_allow_reentry(true))
+                    .try_invoke();
+                self.reentered = Some(matches!(claimed, Ok(Ok(Ok(_)))));
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            _from: AccountId,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            self.balances.insert(to, &(self.balance_of(to) + value));
+            Ok(())
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use ink_e2e::build_message;
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        const INITIAL_POOL: Balance = 100;
+
+        // A platform listing the token as collateral and a news posted by
+        // the token, whose betting and voting end right away.
+        macro_rules! setup {
+            ($client:ident) => {{
+                let cgtoken_code = $client
+                    .upload("cgtoken", &ink_e2e::alice(), None)
+                    .await
+                    .expect("cgtoken upload failed")
+                    .code_hash;
+                let constructor =
+                    CredebilityGuardRef::new(1, 10, 1, 0, 0, INITIAL_POOL, cgtoken_code);
+                let platform = $client
+                    .instantiate("platform", &ink_e2e::alice(), constructor, 0, None)
+                    .await
+                    .expect("platform instantiate failed")
+                    .account_id;
+                let token = $client
+                    .instantiate(
+                        "reentrant_token",
+                        &ink_e2e::alice(),
+                        ReentrantTokenRef::new(),
+                        0,
+                        None,
+                    )
+                    .await
+                    .expect("token instantiate failed")
+                    .account_id;
+                let listed = build_message::<CredebilityGuardRef>(platform.clone())
+                    .call(|platform| platform.set_collateral_token(token.clone(), true));
+                $client
+                    .call(&ink_e2e::alice(), listed, 0, None)
+                    .await
+                    .expect("listing failed");
+                let post = build_message::<ReentrantTokenRef>(token.clone()).call(|token| {
+                    token.post(
+                        platform.clone(),
+                        Metadata {
+                            cid: news_cid::cid(b"reentrant"),
+                            content_type: String::from("text/plain"),
+                            url: None,
+                            language: String::from("en"),
+                        },
+                    )
+                });
+                let id = $client
+                    .call(&ink_e2e::alice(), post, 0, None)
+                    .await
+                    .expect("post failed")
+                    .return_value();
+                (platform, token, id)
+            }};
+        }
+
+        #[ink_e2e::test(additional_contracts = "../Cargo.toml ../cgtoken/Cargo.toml")]
+        async fn payout_cannot_be_reentered(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (platform, token, id) = setup!(client);
+            let attack = build_message::<ReentrantTokenRef>(token.clone())
+                .call(|token| token.set_attack(Some(id)));
+            client
+                .call(&ink_e2e::alice(), attack, 0, None)
+                .await
+                .expect("set_attack failed");
+
+            let claim = build_message::<ReentrantTokenRef>(token.clone())
+                .call(|token| token.pool_claim(platform.clone(), id));
+            let claimed = client
+                .call(&ink_e2e::alice(), claim, 0, None)
+                .await
+                .expect("pool_claim failed")
+                .return_value();
+            assert_eq!(claimed, Ok(INITIAL_POOL));
+
+            // the platform was still paying out when the token called back
+            let reentered = build_message::<ReentrantTokenRef>(token.clone())
+                .call(|token| token.get_reentered());
+            let reentered = client
+                .call_dry_run(&ink_e2e::alice(), &reentered, 0, None)
+                .await
+                .return_value();
+            assert_eq!(reentered, Some(false));
+            let balance = build_message::<ReentrantTokenRef>(token.clone())
+                .call(|contract| contract.balance_of(token.clone()));
+            let balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance, 0, None)
+                .await
+                .return_value();
+            assert_eq!(balance, INITIAL_POOL);
+            let pools = build_message::<CredebilityGuardRef>(platform.clone())
+                .call(|platform| platform.get_pools_containing(Collateral::Token(token.clone())));
+            let pools = client
+                .call_dry_run(&ink_e2e::alice(), &pools, 0, None)
+                .await
+                .return_value();
+            assert_eq!(pools, 0);
+            Ok(())
+        }
+
+        #[ink_e2e::test(additional_contracts = "../Cargo.toml ../cgtoken/Cargo.toml")]
+        async fn refused_payout_stays_pending(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (platform, token, id) = setup!(client);
+            let refuse = build_message::<ReentrantTokenRef>(token.clone())
+                .call(|token| token.set_refuse(true));
+            client
+                .call(&ink_e2e::alice(), refuse, 0, None)
+                .await
+                .expect("set_refuse failed");
+
+            let claim = build_message::<ReentrantTokenRef>(token.clone())
+                .call(|token| token.pool_claim(platform.clone(), id));
+            let claimed = client
+                .call(&ink_e2e::alice(), claim, 0, None)
+                .await
+                .expect("pool_claim failed")
+                .return_value();
+            assert_eq!(claimed, Ok(INITIAL_POOL));
+            let pending = build_message::<CredebilityGuardRef>(platform.clone()).call(|platform| {
+                platform.get_pending_withdrawal(token.clone(), Collateral::Token(token.clone()))
+            });
+            let pending = client
+                .call_dry_run(&ink_e2e::alice(), &pending, 0, None)
+                .await
+                .return_value();
+            assert_eq!(pending, INITIAL_POOL);
+
+            // a withdrawal the token still refuses is reverted with its error
+            let withdraw = build_message::<ReentrantTokenRef>(token.clone())
+                .call(|token| token.withdraw(platform.clone()));
+            let withdrawn = client
+                .call_dry_run(&ink_e2e::alice(), &withdraw, 0, None)
+                .await
+                .return_value();
+            assert_eq!(
+                withdrawn,
+                Err(Error::TokenTransferFailed(PSP22Error::Custom(
+                    String::from("refused")
+                )))
+            );
+
+            let refuse = build_message::<ReentrantTokenRef>(token.clone())
+                .call(|token| token.set_refuse(false));
+            client
+                .call(&ink_e2e::alice(), refuse, 0, None)
+                .await
+                .expect("set_refuse failed");
+            let withdrawn = client
+                .call(&ink_e2e::alice(), withdraw, 0, None)
+                .await
+                .expect("withdraw failed")
+                .return_value();
+            assert_eq!(withdrawn, Ok(INITIAL_POOL));
+            Ok(())
+        }
+    }
+}